@@ -0,0 +1,9 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS journal_fts USING fts5(entry, tags, ai_tags, content='journal_entries', content_rowid='id', tokenize='porter unicode61');
+
+CREATE TRIGGER IF NOT EXISTS journal_fts_after_insert AFTER INSERT ON journal_entries BEGIN INSERT INTO journal_fts (rowid, entry, tags, ai_tags) VALUES (NEW.id, NEW.entry, NEW.tags, NEW.ai_tags); END;
+
+CREATE TRIGGER IF NOT EXISTS journal_fts_after_delete AFTER DELETE ON journal_entries BEGIN INSERT INTO journal_fts (journal_fts, rowid, entry, tags, ai_tags) VALUES ('delete', OLD.id, OLD.entry, OLD.tags, OLD.ai_tags); END;
+
+CREATE TRIGGER IF NOT EXISTS journal_fts_after_update AFTER UPDATE ON journal_entries BEGIN INSERT INTO journal_fts (journal_fts, rowid, entry, tags, ai_tags) VALUES ('delete', OLD.id, OLD.entry, OLD.tags, OLD.ai_tags); INSERT INTO journal_fts (rowid, entry, tags, ai_tags) VALUES (NEW.id, NEW.entry, NEW.tags, NEW.ai_tags); END;
+
+INSERT INTO journal_fts (journal_fts) VALUES ('rebuild');
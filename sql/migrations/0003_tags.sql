@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS tags (id INTEGER CONSTRAINT tags_pk PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL UNIQUE, created_at DATETIME DEFAULT CURRENT_TIMESTAMP);
+
+CREATE TABLE IF NOT EXISTS entry_tags (journal_entry_id INTEGER NOT NULL CONSTRAINT entry_tags_journal_entries_id_fk REFERENCES journal_entries ON DELETE CASCADE, tag_id INTEGER NOT NULL CONSTRAINT entry_tags_tags_id_fk REFERENCES tags ON DELETE CASCADE, source TEXT NOT NULL CHECK (source IN ('user', 'ai')), PRIMARY KEY (journal_entry_id, tag_id, source));
+
+CREATE INDEX IF NOT EXISTS entry_tags_tag_id_index ON entry_tags (tag_id);
+
+-- Split the legacy comma-joined tag strings into one row per tag. 'general' is the
+-- placeholder the analyzer stored when it found no topics, so it is not a real tag.
+CREATE TEMP TABLE legacy_entry_tags AS
+WITH RECURSIVE split (journal_entry_id, source, tag, rest) AS (
+    SELECT id, 'user', '', tags || ',' FROM journal_entries WHERE tags IS NOT NULL
+    UNION ALL
+    SELECT id, 'ai', '', ai_tags || ',' FROM journal_entries WHERE ai_tags IS NOT NULL
+    UNION ALL
+    SELECT journal_entry_id, source, LOWER(TRIM(substr(rest, 1, instr(rest, ',') - 1))), substr(rest, instr(rest, ',') + 1)
+    FROM split WHERE rest != ''
+)
+SELECT DISTINCT journal_entry_id, source, tag FROM split WHERE tag != '' AND NOT (source = 'ai' AND tag = 'general');
+
+INSERT OR IGNORE INTO tags (name) SELECT DISTINCT tag FROM legacy_entry_tags;
+
+INSERT OR IGNORE INTO entry_tags (journal_entry_id, tag_id, source)
+SELECT l.journal_entry_id, t.id, l.source FROM legacy_entry_tags l JOIN tags t ON t.name = l.tag;
+
+DROP TABLE legacy_entry_tags;
@@ -0,0 +1,9 @@
+-- Snippets used to be stored wrapped in ''' lines with every single quote doubled.
+-- Store them verbatim: strip the wrapper and undo the escaping. Every snippet saved
+-- before this migration went through that wrapping, so only rows that still have
+-- the exact wrapper shape are touched.
+UPDATE code_snippets
+SET full_code = REPLACE(substr(full_code, 5, length(full_code) - 8), '''''', '''')
+WHERE length(full_code) >= 8
+  AND substr(full_code, 1, 4) = '''''''' || char(10)
+  AND substr(full_code, -4) = char(10) || '''''''';
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS language_aliases (alias TEXT NOT NULL CONSTRAINT language_aliases_pk PRIMARY KEY, language_id INTEGER NOT NULL CONSTRAINT language_aliases_languages_id_fk REFERENCES languages ON DELETE CASCADE, created_at DATETIME DEFAULT CURRENT_TIMESTAMP);
+
+CREATE INDEX IF NOT EXISTS language_aliases_language_id_index ON language_aliases (language_id);
+
+-- Common alternative spellings of the seeded languages
+INSERT OR IGNORE INTO language_aliases (alias, language_id)
+SELECT aliases.column1, languages.id
+FROM (VALUES ('rs', 'rust'), ('javascript', 'js'), ('typescript', 'ts'), ('py', 'python'), ('cpp', 'c++'),
+             ('golang', 'go'), ('htm', 'html')) AS aliases
+JOIN languages ON languages.name = aliases.column2;
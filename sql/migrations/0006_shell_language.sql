@@ -0,0 +1,8 @@
+-- Shell one-liners are among the most common snippets
+INSERT OR IGNORE INTO languages (name) VALUES ('bash');
+
+INSERT OR IGNORE INTO language_aliases (alias, language_id)
+SELECT aliases.column1, languages.id
+FROM (VALUES ('sh'), ('shell'), ('zsh')) AS aliases
+JOIN languages ON languages.name = 'bash'
+WHERE aliases.column1 NOT IN (SELECT name FROM languages);
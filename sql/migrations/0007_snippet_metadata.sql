@@ -0,0 +1,5 @@
+ALTER TABLE code_snippets ADD COLUMN title TEXT;
+ALTER TABLE code_snippets ADD COLUMN description TEXT;
+ALTER TABLE code_snippets ADD COLUMN source_url TEXT;
+-- Comma-separated, normalized like journal entry tags
+ALTER TABLE code_snippets ADD COLUMN tags TEXT;
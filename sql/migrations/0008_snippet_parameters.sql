@@ -0,0 +1,3 @@
+-- JSON list of the {{placeholders}} in full_code, e.g. [{"name":"port","default":"8080"}].
+-- NULL for snippets saved before this migration; their parameters are detected when read.
+ALTER TABLE code_snippets ADD COLUMN parameters TEXT;
@@ -0,0 +1,3 @@
+CREATE TABLE IF NOT EXISTS snippet_runs (id INTEGER CONSTRAINT snippet_runs_pk PRIMARY KEY AUTOINCREMENT, snippet_id INTEGER NOT NULL CONSTRAINT snippet_runs_code_snippets_id_fk REFERENCES code_snippets ON DELETE CASCADE, command TEXT NOT NULL, exit_code INTEGER, duration_ms INTEGER NOT NULL, ran_at DATETIME DEFAULT CURRENT_TIMESTAMP);
+
+CREATE INDEX IF NOT EXISTS snippet_runs_snippet_id_index ON snippet_runs (snippet_id, ran_at);
@@ -0,0 +1,10 @@
+ALTER TABLE learning_notes ADD COLUMN title TEXT;
+ALTER TABLE learning_notes ADD COLUMN content_hash TEXT;
+ALTER TABLE learning_notes ADD COLUMN word_count INTEGER NOT NULL DEFAULT 0;
+-- JSON array of {"level", "text"} objects
+ALTER TABLE learning_notes ADD COLUMN headings TEXT;
+
+-- Holds the indexed markdown itself, as notes live in files outside the database
+CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(title, headings, body, tokenize='porter unicode61');
+
+CREATE TRIGGER IF NOT EXISTS notes_fts_after_delete AFTER DELETE ON learning_notes BEGIN DELETE FROM notes_fts WHERE rowid = OLD.id; END;
@@ -0,0 +1,4 @@
+-- Scans look notes up by file and by content to skip files that are already known
+CREATE INDEX IF NOT EXISTS learning_notes_file_path_index ON learning_notes (file_path);
+
+CREATE INDEX IF NOT EXISTS learning_notes_content_hash_index ON learning_notes (content_hash);
@@ -0,0 +1,6 @@
+-- Set by `note sync` when a note's file is gone, cleared when it is found again
+ALTER TABLE learning_notes ADD COLUMN missing_since DATETIME;
+
+-- Directories added with `note scan`, searched for notes whose files moved.
+-- excludes is a JSON array of globs.
+CREATE TABLE IF NOT EXISTS note_roots (id INTEGER CONSTRAINT note_roots_pk PRIMARY KEY AUTOINCREMENT, path TEXT NOT NULL UNIQUE, glob TEXT NOT NULL, excludes TEXT NOT NULL DEFAULT '[]', scanned_at DATETIME DEFAULT CURRENT_TIMESTAMP);
@@ -0,0 +1,6 @@
+-- Links are stored as written and resolved to notes when read, so links to notes added later work
+CREATE TABLE IF NOT EXISTS note_links (id INTEGER CONSTRAINT note_links_pk PRIMARY KEY AUTOINCREMENT, source_note_id INTEGER NOT NULL CONSTRAINT note_links_learning_notes_id_fk REFERENCES learning_notes ON DELETE CASCADE, kind TEXT NOT NULL CHECK (kind IN ('wiki', 'markdown')), target TEXT NOT NULL, UNIQUE (source_note_id, kind, target));
+
+-- Links are found when a note is indexed. Notes indexed before this migration have none
+-- yet and get them the next time `note sync`, `note scan` or `watch` finds them unchanged.
+ALTER TABLE learning_notes ADD COLUMN links_indexed_at DATETIME;
@@ -0,0 +1,9 @@
+-- Snippets can be executed, so sh, zsh and fish are languages of their own that run under
+-- their own shell, instead of aliases of bash. Snippets already saved as bash stay bash.
+DELETE FROM language_aliases
+WHERE alias IN ('sh', 'zsh', 'fish') AND language_id = (SELECT id FROM languages WHERE name = 'bash');
+
+INSERT OR IGNORE INTO languages (name)
+SELECT shells.column1
+FROM (VALUES ('sh'), ('zsh'), ('fish')) AS shells
+WHERE shells.column1 NOT IN (SELECT alias FROM language_aliases);
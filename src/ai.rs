@@ -0,0 +1,212 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt;
+use std::time::Duration;
+
+/// Result of running a journal entry through an analyzer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalAnalysis {
+    pub sentiment: String,
+    pub ai_tags: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum AnalyzerError {
+    RequestError(reqwest::Error),
+    InvalidResponse(String),
+}
+
+impl fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzerError::RequestError(err) => write!(f, "AI request failed: {}", err),
+            AnalyzerError::InvalidResponse(msg) => write!(f, "Invalid AI response: {}", msg),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AnalyzerError {
+    fn from(error: reqwest::Error) -> Self {
+        AnalyzerError::RequestError(error)
+    }
+}
+
+/// A backend that derives sentiment and tags from the text of a journal entry.
+pub trait JournalAnalyzer {
+    fn analyze(&self, entry_text: &str) -> Result<JournalAnalysis, AnalyzerError>;
+}
+
+/// Offline analyzer based on simple keyword matching. This is the default backend.
+pub struct KeywordAnalyzer;
+
+impl JournalAnalyzer for KeywordAnalyzer {
+    fn analyze(&self, entry_text: &str) -> Result<JournalAnalysis, AnalyzerError> {
+        let text = entry_text.to_lowercase();
+
+        // Simple sentiment analysis based on keywords
+        let sentiment = if ["happy", "good", "great", "excited"].iter().any(|w| text.contains(w)) {
+            "positive"
+        } else if ["sad", "bad", "terrible", "frustrated"].iter().any(|w| text.contains(w)) {
+            "negative"
+        } else {
+            "neutral"
+        };
+
+        // Simple tag generation based on common programming keywords
+        let keyword_tags = [
+            ("rust", "rust"),
+            ("project", "project"),
+            ("bug", "debugging"),
+            ("learn", "learning"),
+            ("code", "coding"),
+            ("work", "work"),
+        ];
+        let ai_tags = keyword_tags
+            .iter()
+            .filter(|(keyword, _)| text.contains(keyword))
+            .map(|(_, tag)| tag.to_string())
+            .collect();
+
+        Ok(JournalAnalysis {
+            sentiment: sentiment.to_string(),
+            ai_tags,
+        })
+    }
+}
+
+const SYSTEM_PROMPT: &str = "You analyze developer journal entries. \
+Reply with only a JSON object of the form {\"sentiment\": \"positive\" | \"negative\" | \"neutral\", \"tags\": [\"...\"]} \
+where tags are at most five short lowercase topics (languages, tools, activities) mentioned in the entry.";
+
+/// Analyzer that talks to an OpenAI-compatible `/chat/completions` endpoint.
+pub struct OpenAiAnalyzer {
+    client: reqwest::blocking::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiAnalyzer {
+    pub fn new(
+        base_url: &str,
+        model: &str,
+        api_key: Option<String>,
+        timeout: Duration,
+    ) -> Result<Self, AnalyzerError> {
+        let client = reqwest::blocking::Client::builder().timeout(timeout).build()?;
+        Ok(OpenAiAnalyzer {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ChatCompletion {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct RawAnalysis {
+    sentiment: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl JournalAnalyzer for OpenAiAnalyzer {
+    fn analyze(&self, entry_text: &str) -> Result<JournalAnalysis, AnalyzerError> {
+        let body = json!({
+            "model": self.model,
+            "temperature": 0,
+            "messages": [
+                {"role": "system", "content": SYSTEM_PROMPT},
+                {"role": "user", "content": entry_text},
+            ],
+        });
+
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let completion: ChatCompletion = request.send()?.error_for_status()?.json()?;
+        let content = completion
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| AnalyzerError::InvalidResponse("no choices returned".to_string()))?;
+
+        parse_analysis(&content)
+    }
+}
+
+/// Parses the JSON object returned by the model, tolerating a surrounding markdown code fence.
+fn parse_analysis(content: &str) -> Result<JournalAnalysis, AnalyzerError> {
+    let trimmed = content.trim();
+    let json_text = match (trimmed.find('{'), trimmed.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => return Err(AnalyzerError::InvalidResponse(format!("expected a JSON object, got: {}", trimmed))),
+    };
+
+    let raw: RawAnalysis = serde_json::from_str(json_text)
+        .map_err(|e| AnalyzerError::InvalidResponse(e.to_string()))?;
+
+    let sentiment = raw.sentiment.trim().to_lowercase();
+    if !matches!(sentiment.as_str(), "positive" | "negative" | "neutral") {
+        return Err(AnalyzerError::InvalidResponse(format!("unknown sentiment: {}", raw.sentiment)));
+    }
+
+    let mut ai_tags: Vec<String> = Vec::new();
+    for tag in raw.tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !ai_tags.contains(&tag) {
+            ai_tags.push(tag);
+        }
+    }
+
+    Ok(JournalAnalysis { sentiment, ai_tags })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_analyzer() {
+        let analysis = KeywordAnalyzer
+            .analyze("Fixed a nasty bug in my Rust project, feeling great")
+            .unwrap();
+        assert_eq!(analysis.sentiment, "positive");
+        assert_eq!(analysis.ai_tags, vec!["rust", "project", "debugging"]);
+    }
+
+    #[test]
+    fn test_parse_analysis_with_code_fence() {
+        let content = "```json\n{\"sentiment\": \"Negative\", \"tags\": [\"Rust\", \"async\", \"rust\"]}\n```";
+        let analysis = parse_analysis(content).unwrap();
+        assert_eq!(analysis.sentiment, "negative");
+        assert_eq!(analysis.ai_tags, vec!["rust", "async"]);
+    }
+
+    #[test]
+    fn test_parse_analysis_rejects_unknown_sentiment() {
+        assert!(parse_analysis("{\"sentiment\": \"ecstatic\", \"tags\": []}").is_err());
+        assert!(parse_analysis("I think this entry is positive").is_err());
+    }
+}
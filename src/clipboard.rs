@@ -0,0 +1,196 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Environment variables overriding the clipboard commands, e.g. `CLINOTES_CLIPBOARD_COPY="xclip -i"`.
+pub const COPY_ENV: &str = "CLINOTES_CLIPBOARD_COPY";
+pub const PASTE_ENV: &str = "CLINOTES_CLIPBOARD_PASTE";
+
+/// The system clipboard, reached through external programs. Without a copy program,
+/// text is copied with the OSC 52 terminal escape sequence, which also works over SSH.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clipboard {
+    copy: Option<Vec<String>>,
+    paste: Option<Vec<String>>,
+}
+
+impl Clipboard {
+    /// Uses the commands from `CLINOTES_CLIPBOARD_COPY` / `CLINOTES_CLIPBOARD_PASTE` when set,
+    /// otherwise the first of `wl-copy`, `pbcopy`, `xclip` and `xsel` that is installed.
+    pub fn from_env() -> Self {
+        let (copy, paste) = detect_commands().unwrap_or((None, None));
+        Clipboard {
+            copy: command_from_env(COPY_ENV).or(copy),
+            paste: command_from_env(PASTE_ENV).or(paste),
+        }
+    }
+
+    pub fn with_commands(copy: &[&str], paste: &[&str]) -> Self {
+        let owned = |command: &[&str]| Some(command.iter().map(|part| part.to_string()).collect());
+        Clipboard {
+            copy: owned(copy),
+            paste: owned(paste),
+        }
+    }
+
+    /// How text gets copied, for messages like "Copied (via xclip)".
+    pub fn copy_method(&self) -> &str {
+        match &self.copy {
+            Some(command) => &command[0],
+            None => "terminal OSC 52",
+        }
+    }
+
+    pub fn copy(&self, text: &str) -> io::Result<()> {
+        let command = match &self.copy {
+            Some(command) => command,
+            None => return copy_osc52(text),
+        };
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to run '{}': {}", command.join(" "), e)))?;
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(text.as_bytes())?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("'{}' exited with {}", command.join(" "), status)));
+        }
+        Ok(())
+    }
+
+    pub fn paste(&self) -> io::Result<String> {
+        let command = self.paste.as_ref().ok_or_else(|| {
+            io::Error::other(format!(
+                "No clipboard program found. Install wl-clipboard, xclip or xsel, or set {}",
+                PASTE_ENV
+            ))
+        })?;
+        let output = Command::new(&command[0])
+            .args(&command[1..])
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to run '{}': {}", command.join(" "), e)))?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "'{}' exited with {}",
+                command.join(" "),
+                output.status
+            )));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "The clipboard does not contain text"))
+    }
+}
+
+fn command_from_env(var: &str) -> Option<Vec<String>> {
+    let value = env::var(var).ok()?;
+    let parts: Vec<String> = value.split_whitespace().map(String::from).collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts)
+    }
+}
+
+type Commands = (Option<Vec<String>>, Option<Vec<String>>);
+
+fn detect_commands() -> Option<Commands> {
+    let command = |parts: &[&str]| Some(parts.iter().map(|part| part.to_string()).collect());
+    if env::var_os("WAYLAND_DISPLAY").is_some() && on_path("wl-copy") {
+        return Some((command(&["wl-copy"]), command(&["wl-paste", "--no-newline"])));
+    }
+    if on_path("pbcopy") {
+        return Some((command(&["pbcopy"]), command(&["pbpaste"])));
+    }
+    if env::var_os("DISPLAY").is_some() {
+        if on_path("xclip") {
+            return Some((
+                command(&["xclip", "-selection", "clipboard", "-in"]),
+                command(&["xclip", "-selection", "clipboard", "-out"]),
+            ));
+        }
+        if on_path("xsel") {
+            return Some((
+                command(&["xsel", "--clipboard", "--input"]),
+                command(&["xsel", "--clipboard", "--output"]),
+            ));
+        }
+    }
+    None
+}
+
+fn on_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Asks the terminal to put `text` on the clipboard.
+fn copy_osc52(text: &str) -> io::Result<()> {
+    let mut tty = OpenOptions::new()
+        .write(true)
+        .open("/dev/tty")
+        .map_err(|e| io::Error::new(e.kind(), format!("No clipboard program or terminal available: {}", e)))?;
+    write!(tty, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    tty.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64("println!(\"é\")".as_bytes()), "cHJpbnRsbiEoIsOpIik=");
+    }
+
+    #[test]
+    fn test_copy_and_paste_through_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("clipboard");
+        let file = file.to_str().unwrap();
+        let copy = format!("cat > {}", file);
+        let paste = format!("cat {}", file);
+        let clipboard = Clipboard::with_commands(&["sh", "-c", &copy], &["sh", "-c", &paste]);
+
+        clipboard.copy("let x = 'quoted';\n").unwrap();
+        assert_eq!(clipboard.paste().unwrap(), "let x = 'quoted';\n");
+        assert_eq!(clipboard.copy_method(), "sh");
+    }
+
+    #[test]
+    fn test_failing_command_is_an_error() {
+        let clipboard = Clipboard::with_commands(&["false"], &["false"]);
+        assert!(clipboard.copy("x").is_err());
+        assert!(clipboard.paste().is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::ai::{AnalyzerError, JournalAnalyzer, KeywordAnalyzer, OpenAiAnalyzer};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// User configuration, read from `config.json` in the clinotes config directory.
+///
+/// ```json
+/// {
+///   "ai": {
+///     "backend": "openai",
+///     "base_url": "http://localhost:11434/v1",
+///     "model": "llama3",
+///     "api_key_env": "OPENAI_API_KEY"
+///   }
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub ai: AiConfig,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum AiConfig {
+    #[default]
+    Keyword,
+    OpenAi {
+        base_url: String,
+        model: String,
+        /// Name of the environment variable holding the API key, if the endpoint needs one.
+        api_key_env: Option<String>,
+        #[serde(default = "default_timeout_secs")]
+        timeout_secs: u64,
+    },
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    IoError(std::io::Error),
+    ParseError(serde_json::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::IoError(err) => write!(f, "Failed to read config: {}", err),
+            ConfigError::ParseError(err) => write!(f, "Failed to parse config: {}", err),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file at `path`, falling back to the defaults when it does not exist.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(path).map_err(ConfigError::IoError)?;
+        serde_json::from_str(&content).map_err(ConfigError::ParseError)
+    }
+}
+
+impl AiConfig {
+    /// Builds the journal analyzer selected by this config.
+    pub fn build_analyzer(&self) -> Result<Box<dyn JournalAnalyzer>, AnalyzerError> {
+        match self {
+            AiConfig::Keyword => Ok(Box::new(KeywordAnalyzer)),
+            AiConfig::OpenAi {
+                base_url,
+                model,
+                api_key_env,
+                timeout_secs,
+            } => {
+                let api_key = api_key_env.as_ref().and_then(|var| std::env::var(var).ok());
+                let analyzer =
+                    OpenAiAnalyzer::new(base_url, model, api_key, Duration::from_secs(*timeout_secs))?;
+                Ok(Box::new(analyzer))
+            }
+        }
+    }
+}
@@ -1,70 +1,38 @@
-use crate::models::{CodeSnippet, JournalEntry, JournalSummary, LearningNote};
-use rusqlite::{params, Connection, OptionalExtension};
+use crate::ai::{AnalyzerError, JournalAnalyzer, KeywordAnalyzer};
+use crate::date_range::DateRange;
+use crate::detect::{self, Detection};
+use crate::markdown;
+use crate::scan;
+use crate::template;
+use crate::models::{
+    CodeSnippet, JournalEntry, JournalSearchResult, JournalSummary, Language, LearningNote, LinkTarget, NoteImport,
+    NoteLink, NoteLinkKind, NoteRoot, NoteSearchResult, NoteSync, OrphanRepair, OrphanRow, SnippetRun, SnippetUsed, Tag,
+    TagSource, TagUsage,
+};
+use rusqlite::{params, Connection, OptionalExtension, Row};
 use std::fmt;
+use std::time::Duration;
+
+use std::path::{Path, PathBuf};
 
-use std::path::Path;
-
-
-// AI placeholder function - simulates AI processing
-pub fn call_journal_ai(entry_text: &str) -> (String, String) {
-    // This is a placeholder implementation
-    // In a real implementation, this would call an external AI service
-    
-    // Simple sentiment analysis based on keywords
-    let sentiment = if entry_text.to_lowercase().contains("happy") || 
-                      entry_text.to_lowercase().contains("good") || 
-                      entry_text.to_lowercase().contains("great") ||
-                      entry_text.to_lowercase().contains("excited") {
-        "positive".to_string()
-    } else if entry_text.to_lowercase().contains("sad") || 
-              entry_text.to_lowercase().contains("bad") || 
-              entry_text.to_lowercase().contains("terrible") ||
-              entry_text.to_lowercase().contains("frustrated") {
-        "negative".to_string()
-    } else {
-        "neutral".to_string()
-    };
-    
-    // Simple tag generation based on common programming keywords
-    let mut ai_tags = Vec::new();
-    if entry_text.to_lowercase().contains("rust") { ai_tags.push("rust"); }
-    if entry_text.to_lowercase().contains("project") { ai_tags.push("project"); }
-    if entry_text.to_lowercase().contains("bug") { ai_tags.push("debugging"); }
-    if entry_text.to_lowercase().contains("learn") { ai_tags.push("learning"); }
-    if entry_text.to_lowercase().contains("code") { ai_tags.push("coding"); }
-    if entry_text.to_lowercase().contains("work") { ai_tags.push("work"); }
-    
-    let ai_tags_string = if ai_tags.is_empty() {
-        "general".to_string()
-    } else {
-        ai_tags.join(",")
-    };
-    
-    (sentiment, ai_tags_string)
-}
 
-pub fn preprocess_code(code: &str, language: &str) -> Result<String, String> {
-    match language {
-        "rust" | "ts" | "js" => {
-            let escaped_code = code.replace("'", "''");
-            Ok(format!("'''\n{}\n'''", escaped_code))
-        }
-        _ => Err("Unsupported language.".to_string()),
-    }
-}
 
 // Custom error type for our DAO operations
 #[derive(Debug)]
 pub enum DaoError {
-    PreprocessingError(String),
     DatabaseError(rusqlite::Error),
+    AnalysisError(AnalyzerError),
+    NotFound(String),
+    InvalidInput(String),
 }
 
 impl fmt::Display for DaoError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            DaoError::PreprocessingError(msg) => write!(f, "Preprocessing error: {}", msg),
             DaoError::DatabaseError(err) => write!(f, "Database error: {}", err),
+            DaoError::AnalysisError(err) => write!(f, "Analysis error: {}", err),
+            DaoError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            DaoError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
         }
     }
 }
@@ -75,48 +43,155 @@ impl From<rusqlite::Error> for DaoError {
     }
 }
 
-//Languages only READ operation available
+impl From<AnalyzerError> for DaoError {
+    fn from(error: AnalyzerError) -> Self {
+        DaoError::AnalysisError(error)
+    }
+}
+
+// Languages and their aliases
 
 pub fn get_language_from_id(conn: &Connection, language_id: i64) -> Result<String, DaoError> {
     conn.query_row(
         "SELECT name FROM languages WHERE id = ?",
         params![language_id],
-        |row| Ok(row.get::<_, String>(0)?),
+        |row| row.get::<_, String>(0),
     ).map_err(DaoError::from)
 }
 
+/// Resolves a language name or alias (case-insensitive) to the language's ID.
 pub fn get_language_id_from_name(conn: &Connection, lang_name: &str) -> Result<i64, DaoError> {
+    let name = normalize_language_name(lang_name);
     conn.query_row(
-        "SELECT id FROM languages WHERE name = ?",
-        params![lang_name],
-        |row| Ok(row.get::<_, i64>(0)?),
-    ).map_err(DaoError::from)
+        "SELECT id FROM languages WHERE name = ?1
+         UNION ALL
+         SELECT language_id FROM language_aliases WHERE alias = ?1
+         LIMIT 1",
+        params![name],
+        |row| row.get::<_, i64>(0),
+    )
+    .optional()?
+    .ok_or_else(|| DaoError::NotFound(format!("Language '{}' does not exist", name)))
 }
 
+/// Whether `language_name` is a known language or alias.
 pub fn language_exists(conn: &Connection, language_name: &str) -> Result<bool, DaoError> {
-    let count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM languages WHERE name = ?",
-        params![language_name],
+    match get_language_id_from_name(conn, language_name) {
+        Ok(_) => Ok(true),
+        Err(DaoError::NotFound(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn normalize_language_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Normalizes a new language name or alias, rejecting empty ones, ones containing
+/// whitespace or commas, and ones already taken by a language or alias.
+fn new_language_name(conn: &Connection, name: &str) -> Result<String, DaoError> {
+    let name = normalize_language_name(name);
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == ',') {
+        return Err(DaoError::InvalidInput(format!(
+            "'{}' is not a valid language name; use a single word like 'python' or 'c++'",
+            name
+        )));
+    }
+    if language_exists(conn, &name)? {
+        return Err(DaoError::InvalidInput(format!("'{}' is already a language or alias", name)));
+    }
+    Ok(name)
+}
+
+pub fn create_language(conn: &Connection, name: &str) -> Result<i64, DaoError> {
+    let name = new_language_name(conn, name)?;
+    conn.execute("INSERT INTO languages (name) VALUES (?1)", params![name])?;
+    Ok(conn.last_insert_rowid())
+}
 
+/// Every language with its aliases and the number of snippets written in it, by name.
+pub fn list_languages(conn: &Connection) -> Result<Vec<(Language, usize)>, DaoError> {
+    let mut stmt = conn.prepare(
+        "SELECT l.id, l.name, l.created_at, l.updated_at,
+                (SELECT GROUP_CONCAT(alias) FROM language_aliases WHERE language_id = l.id),
+                (SELECT COUNT(*) FROM code_snippets WHERE language_id = l.id)
+         FROM languages l
+         ORDER BY l.name",
+    )?;
+    let language_iter = stmt.query_map([], |row| {
+        let aliases: Option<String> = row.get(4)?;
+        let mut aliases: Vec<String> = aliases
+            .map(|aliases| aliases.split(',').map(String::from).collect())
+            .unwrap_or_default();
+        aliases.sort();
+        let language = Language {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            aliases,
+            created_at: row.get(2)?,
+            updated_at: row.get(3)?,
+        };
+        Ok((language, row.get(5)?))
+    })?;
+
+    let mut languages = Vec::new();
+    for language in language_iter {
+        languages.push(language?);
+    }
+    Ok(languages)
+}
+
+/// Removes a language and its aliases. Languages still used by snippets are kept.
+pub fn delete_language(conn: &Connection, name: &str) -> Result<(), DaoError> {
+    let name = normalize_language_name(name);
+    let language_id: i64 = conn
+        .query_row("SELECT id FROM languages WHERE name = ?1", params![name], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| DaoError::NotFound(format!("Language '{}' does not exist", name)))?;
+    let snippets: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM code_snippets WHERE language_id = ?1",
+        params![language_id],
         |row| row.get(0),
     )?;
-    Ok(count > 0)
+    if snippets > 0 {
+        return Err(DaoError::InvalidInput(format!(
+            "Language '{}' is used by {} snippet(s)",
+            name, snippets
+        )));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM language_aliases WHERE language_id = ?1", params![language_id])?;
+    tx.execute("DELETE FROM languages WHERE id = ?1", params![language_id])?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Makes `alias` resolve to `language`, which may itself be given by an alias.
+pub fn add_language_alias(conn: &Connection, alias: &str, language: &str) -> Result<(), DaoError> {
+    let language_id = get_language_id_from_name(conn, language)?;
+    let alias = new_language_name(conn, alias)?;
+    conn.execute(
+        "INSERT INTO language_aliases (alias, language_id) VALUES (?1, ?2)",
+        params![alias, language_id],
+    )?;
+    Ok(())
 }
 
+pub fn remove_language_alias(conn: &Connection, alias: &str) -> Result<(), DaoError> {
+    let alias = normalize_language_name(alias);
+    let removed = conn.execute("DELETE FROM language_aliases WHERE alias = ?1", params![alias])?;
+    if removed == 0 {
+        return Err(DaoError::NotFound(format!("Alias '{}' does not exist", alias)));
+    }
+    Ok(())
+}
 
 pub fn read_learning_note(conn: &Connection, note_id: i64) -> Result<Option<LearningNote>, DaoError> {
     conn.query_row(
-        "SELECT id, file_path, file_name, created_at, updated_at FROM learning_notes WHERE id = ?1",
+        &format!("SELECT {} FROM learning_notes WHERE id = ?1", NOTE_COLUMNS),
         params![note_id],
-        |row| {
-            Ok(LearningNote {
-                id: row.get(0)?,
-                file_path: row.get(1)?,
-                file_name: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        },
+        learning_note_from_row,
     )
     .optional()
     .map_err(DaoError::from)
@@ -124,20 +199,59 @@ pub fn read_learning_note(conn: &Connection, note_id: i64) -> Result<Option<Lear
 
 
 
+/// Guesses the language of `code` among the known languages. `file_name` is the
+/// file the code was read from, if any.
+pub fn detect_snippet_language(
+    conn: &Connection,
+    code: &str,
+    file_name: Option<&str>,
+) -> Result<Option<Detection>, DaoError> {
+    let languages: Vec<Language> = list_languages(conn)?.into_iter().map(|(language, _)| language).collect();
+    Ok(detect::detect_language(code, file_name, &languages))
+}
+
 // CRUD for Code Snippets
+
+/// Stores a snippet in `lang_name` (a name or alias). Without a language it is
+/// detected from the code and `file_name`, the file the code was read from if any,
+/// and the snippet is refused unless the guess is confident.
 pub fn create_code_snippet(
     conn: &Connection,
     snippet: &CodeSnippet,
-    lang_name: &str,
+    lang_name: Option<&str>,
+    file_name: Option<&str>,
 ) -> Result<i64, DaoError> {
-    let lang_id = get_language_id_from_name(conn, lang_name)?;
-
-    let processed_code = preprocess_code(&snippet.full_code, lang_name)
-        .map_err(DaoError::PreprocessingError)?;
+    let lang_id = match lang_name {
+        Some(lang_name) => get_language_id_from_name(conn, lang_name)?,
+        None => match detect_snippet_language(conn, &snippet.full_code, file_name)? {
+            Some(detection) if detection.is_confident() => get_language_id_from_name(conn, &detection.language)?,
+            Some(detection) => {
+                return Err(DaoError::InvalidInput(format!(
+                    "Not sure about the snippet's language (best guess: {}, {:.0}% confident); specify it explicitly",
+                    detection.language,
+                    detection.confidence * 100.0
+                )))
+            }
+            None => {
+                return Err(DaoError::InvalidInput(
+                    "Could not detect the snippet's language; specify it explicitly".to_string(),
+                ))
+            }
+        },
+    };
 
     conn.execute(
-        "INSERT INTO code_snippets (full_code, language_id) VALUES (?, ?)",
-        params![processed_code, lang_id],
+        "INSERT INTO code_snippets (full_code, language_id, title, description, source_url, tags, parameters)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![
+            &snippet.full_code,
+            lang_id,
+            non_empty(&snippet.title),
+            non_empty(&snippet.description),
+            non_empty(&snippet.source_url),
+            snippet_tags_string(&snippet.tags),
+            parameters_json(&snippet.full_code),
+        ],
     )?;
 
     Ok(conn.last_insert_rowid())
@@ -148,17 +262,9 @@ pub fn read_code_snippet(
     snippet_id: i64,
 ) -> Result<Option<CodeSnippet>, DaoError> {
     conn.query_row(
-        "SELECT id, full_code, created_at, updated_at, language_id FROM code_snippets WHERE id = ?1",
+        &format!("SELECT {} FROM code_snippets WHERE id = ?1", SNIPPET_COLUMNS),
         params![snippet_id],
-        |row| {
-            Ok(CodeSnippet {
-                id: row.get(0)?,
-                full_code: row.get(1)?,
-                created_at: row.get(2)?,
-                updated_at: row.get(3)?,
-                language_id: row.get(4)?,
-            })
-        },
+        code_snippet_from_row,
     ).optional().map_err(DaoError::from)
 }
 
@@ -167,123 +273,773 @@ pub fn update_code_snippet(
     snippet: &CodeSnippet,
     lang_name: &str,
 ) -> Result<(), DaoError> {
-    let processed_code = preprocess_code(&snippet.full_code, lang_name)
-        .map_err(DaoError::PreprocessingError)?;
+    let lang_id = get_language_id_from_name(conn, lang_name)?;
 
     conn.execute(
-        "UPDATE code_snippets SET full_code = ?, language_id = ? WHERE id = ?",
-        params![&processed_code, &snippet.language_id, &snippet.id],
+        "UPDATE code_snippets
+         SET full_code = ?, language_id = ?, title = ?, description = ?, source_url = ?, tags = ?, parameters = ?
+         WHERE id = ?",
+        params![
+            &snippet.full_code,
+            lang_id,
+            non_empty(&snippet.title),
+            non_empty(&snippet.description),
+            non_empty(&snippet.source_url),
+            snippet_tags_string(&snippet.tags),
+            parameters_json(&snippet.full_code),
+            &snippet.id,
+        ],
     )?;
     Ok(())
 }
 
+/// Deletes a code snippet together with the `snippets_used` rows linking it and its run history.
 pub fn delete_code_snippet(conn: &Connection, snippet_id: i64) -> Result<(), DaoError> {
-    conn.execute(
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM snippets_used WHERE snippet_id = ?", params![snippet_id])?;
+    tx.execute("DELETE FROM snippet_runs WHERE snippet_id = ?", params![snippet_id])?;
+    let deleted = tx.execute(
         "DELETE FROM code_snippets WHERE id = ?",
         params![snippet_id],
     )?;
+    if deleted == 0 {
+        return Err(DaoError::NotFound(format!("Snippet {} does not exist", snippet_id)));
+    }
+    tx.commit()?;
     Ok(())
 }
 
+/// The most recent snippets, optionally only those in language `lang_name` (a name or alias).
+pub fn list_code_snippets(
+    conn: &Connection,
+    lang_name: Option<&str>,
+    limit: usize,
+) -> Result<Vec<CodeSnippet>, DaoError> {
+    let language_id = lang_name.map(|name| get_language_id_from_name(conn, name)).transpose()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM code_snippets
+         WHERE ?1 IS NULL OR language_id = ?1
+         ORDER BY created_at DESC, id DESC
+         LIMIT ?2",
+        SNIPPET_COLUMNS
+    ))?;
+    let snippet_iter = stmt.query_map(params![language_id, limit as i64], code_snippet_from_row)?;
+
+    let mut snippets = Vec::new();
+    for snippet in snippet_iter {
+        snippets.push(snippet?);
+    }
+    Ok(snippets)
+}
+
+/// Snippets whose code, title, description or source URL contain `text`, or that
+/// have it as a tag, ignoring case, newest first.
+pub fn search_code_snippets(conn: &Connection, text: &str) -> Result<Vec<CodeSnippet>, DaoError> {
+    // Tags are stored as "a, b", so padding both sides with the separator matches whole tags only
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM code_snippets
+         WHERE instr(LOWER(full_code), LOWER(?1)) > 0
+            OR instr(LOWER(COALESCE(title, '')), LOWER(?1)) > 0
+            OR instr(LOWER(COALESCE(description, '')), LOWER(?1)) > 0
+            OR instr(LOWER(COALESCE(source_url, '')), LOWER(?1)) > 0
+            OR (?2 != '' AND instr(', ' || COALESCE(tags, '') || ', ', ', ' || ?2 || ', ') > 0)
+         ORDER BY created_at DESC, id DESC",
+        SNIPPET_COLUMNS
+    ))?;
+    let snippet_iter = stmt.query_map(params![text, normalize_tag_name(text)], code_snippet_from_row)?;
+
+    let mut snippets = Vec::new();
+    for snippet in snippet_iter {
+        snippets.push(snippet?);
+    }
+    Ok(snippets)
+}
+
+/// Records that `command`, rendered from snippet `snippet_id`, ran for `duration`.
+pub fn record_snippet_run(
+    conn: &Connection,
+    snippet_id: i64,
+    command: &str,
+    exit_code: Option<i32>,
+    duration: Duration,
+) -> Result<i64, DaoError> {
+    conn.execute(
+        "INSERT INTO snippet_runs (snippet_id, command, exit_code, duration_ms) VALUES (?1, ?2, ?3, ?4)",
+        params![snippet_id, command, exit_code, duration.as_millis() as i64],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// The most recent runs of a snippet, newest first.
+pub fn list_snippet_runs(conn: &Connection, snippet_id: i64, limit: usize) -> Result<Vec<SnippetRun>, DaoError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, snippet_id, command, exit_code, duration_ms, ran_at FROM snippet_runs
+         WHERE snippet_id = ?1
+         ORDER BY ran_at DESC, id DESC
+         LIMIT ?2",
+    )?;
+    let run_iter = stmt.query_map(params![snippet_id, limit as i64], |row| {
+        Ok(SnippetRun {
+            id: row.get(0)?,
+            snippet_id: row.get(1)?,
+            command: row.get(2)?,
+            exit_code: row.get(3)?,
+            duration_ms: row.get(4)?,
+            ran_at: row.get(5)?,
+        })
+    })?;
+
+    let mut runs = Vec::new();
+    for run in run_iter {
+        runs.push(run?);
+    }
+    Ok(runs)
+}
+
+pub fn count_snippet_runs(conn: &Connection, snippet_id: i64) -> Result<usize, DaoError> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM snippet_runs WHERE snippet_id = ?1",
+        params![snippet_id],
+        |row| row.get(0),
+    )
+    .map_err(DaoError::from)
+}
+
+// Links between snippets and the journal entries / learning notes they were used in
+
+/// The `snippets_used` column holding the ID of `target`.
+fn link_column(target: LinkTarget) -> (&'static str, i64) {
+    match target {
+        LinkTarget::Journal(id) => ("journal_entry_id", id),
+        LinkTarget::Note(id) => ("learning_note_id", id),
+    }
+}
+
+/// Records that snippet `snippet_id` was used in `target`. Both must exist, and a
+/// snippet is linked to the same target at most once.
+pub fn link_snippet(
+    conn: &Connection,
+    snippet_id: i64,
+    target: LinkTarget,
+    description: Option<&str>,
+) -> Result<i64, DaoError> {
+    if read_code_snippet(conn, snippet_id)?.is_none() {
+        return Err(DaoError::NotFound(format!("Snippet {} does not exist", snippet_id)));
+    }
+    let target_exists = match target {
+        LinkTarget::Journal(id) => read_journal_entry(conn, id)?.is_some(),
+        LinkTarget::Note(id) => read_learning_note(conn, id)?.is_some(),
+    };
+    if !target_exists {
+        return Err(DaoError::NotFound(format!("{} does not exist", describe_link_target(target))));
+    }
+
+    let (column, target_id) = link_column(target);
+    let linked: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM snippets_used WHERE snippet_id = ?1 AND {} = ?2)", column),
+        params![snippet_id, target_id],
+        |row| row.get(0),
+    )?;
+    if linked {
+        return Err(DaoError::InvalidInput(format!(
+            "Snippet {} is already linked to {}",
+            snippet_id,
+            describe_link_target(target)
+        )));
+    }
+
+    conn.execute(
+        &format!("INSERT INTO snippets_used (snippet_id, {}, description) VALUES (?1, ?2, ?3)", column),
+        params![snippet_id, target_id, description.map(str::trim).filter(|d| !d.is_empty())],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn unlink_snippet(conn: &Connection, snippet_id: i64, target: LinkTarget) -> Result<(), DaoError> {
+    let (column, target_id) = link_column(target);
+    let removed = conn.execute(
+        &format!("DELETE FROM snippets_used WHERE snippet_id = ?1 AND {} = ?2", column),
+        params![snippet_id, target_id],
+    )?;
+    if removed == 0 {
+        return Err(DaoError::NotFound(format!(
+            "Snippet {} is not linked to {}",
+            snippet_id,
+            describe_link_target(target)
+        )));
+    }
+    Ok(())
+}
+
+/// Where a snippet was used: its links to journal entries and learning notes.
+pub fn list_snippet_links(conn: &Connection, snippet_id: i64) -> Result<Vec<SnippetUsed>, DaoError> {
+    query_snippet_links(conn, "snippet_id = ?1", snippet_id)
+}
+
+/// The snippets linked to a journal entry or learning note.
+pub fn list_linked_snippets(conn: &Connection, target: LinkTarget) -> Result<Vec<SnippetUsed>, DaoError> {
+    let (column, target_id) = link_column(target);
+    query_snippet_links(conn, &format!("{} = ?1", column), target_id)
+}
+
+fn query_snippet_links(conn: &Connection, filter: &str, id: i64) -> Result<Vec<SnippetUsed>, DaoError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, snippet_id, description, learning_note_id, journal_entry_id FROM snippets_used
+         WHERE {}
+         ORDER BY id",
+        filter
+    ))?;
+    let link_iter = stmt.query_map(params![id], |row| {
+        Ok(SnippetUsed {
+            id: row.get(0)?,
+            snippet_id: row.get(1)?,
+            description: row.get(2)?,
+            learning_note_id: row.get(3)?,
+            journal_entry_id: row.get(4)?,
+        })
+    })?;
+
+    let mut links = Vec::new();
+    for link in link_iter {
+        links.push(link?);
+    }
+    Ok(links)
+}
+
+fn describe_link_target(target: LinkTarget) -> String {
+    match target {
+        LinkTarget::Journal(id) => format!("journal entry {}", id),
+        LinkTarget::Note(id) => format!("learning note {}", id),
+    }
+}
+
+const SNIPPET_COLUMNS: &str =
+    "id, full_code, created_at, updated_at, language_id, title, description, source_url, tags, parameters";
+
+fn code_snippet_from_row(row: &Row) -> rusqlite::Result<CodeSnippet> {
+    let full_code: String = row.get(1)?;
+    let parameters: Option<String> = row.get(9)?;
+    // Snippets saved before parameters were stored have none recorded
+    let parameters = match parameters {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(9, rusqlite::types::Type::Text, Box::new(e)))?,
+        None => template::parameters(&full_code),
+    };
+    Ok(CodeSnippet {
+        id: row.get(0)?,
+        full_code,
+        created_at: row.get(2)?,
+        updated_at: row.get(3)?,
+        language_id: row.get(4)?,
+        title: row.get(5)?,
+        description: row.get(6)?,
+        source_url: row.get(7)?,
+        tags: row.get(8)?,
+        parameters,
+    })
+}
+
+/// The placeholders of `code` as stored in the `parameters` column.
+fn parameters_json(code: &str) -> String {
+    serde_json::to_string(&template::parameters(code)).expect("parameters serialize to JSON")
+}
+
+/// Trimmed text, or `None` when it is blank.
+fn non_empty(text: &Option<String>) -> Option<&str> {
+    text.as_deref().map(str::trim).filter(|text| !text.is_empty())
+}
+
+/// Snippet tags normalized the same way as journal tags, or `None` when there are none.
+fn snippet_tags_string(tags: &Option<String>) -> Option<String> {
+    let names = parse_tag_names(tags.as_deref().unwrap_or(""));
+    if names.is_empty() {
+        None
+    } else {
+        Some(names.join(", "))
+    }
+}
+
 //CRUD for journal entries
 pub fn create_journal_entry(conn: &Connection, journal_entry: &JournalEntry) -> Result<i64, DaoError> {
+    create_journal_entry_with_analyzer(conn, journal_entry, &KeywordAnalyzer)
+}
+
+/// Creates a journal entry, using `analyzer` to derive its sentiment and AI tags.
+pub fn create_journal_entry_with_analyzer(
+    conn: &Connection,
+    journal_entry: &JournalEntry,
+    analyzer: &dyn JournalAnalyzer,
+) -> Result<i64, DaoError> {
     // Use the tag string directly from the journal_entry struct
     let tags = match &journal_entry.tags {
         None => String::new(),
         Some(tag) => tag.clone(),  // Just clone the tag without preprocessing
     };
 
-    // Call AI function to get sentiment and AI tags
-    let (sentiment, ai_tags) = call_journal_ai(&journal_entry.entry);
+    let analysis = analyzer.analyze(&journal_entry.entry)?;
+    let sentiment = &analysis.sentiment;
+    let ai_tags = ai_tags_string(&analysis.ai_tags);
 
-    conn.execute(
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
         "INSERT INTO journal_entries (entry, tags, sentiment, ai_tags) VALUES (?, ?, ?, ?)",
         params![&journal_entry.entry, tags, sentiment, ai_tags],
     )?;
-    Ok(conn.last_insert_rowid())
+    let entry_id = tx.last_insert_rowid();
+
+    attach_tags(&tx, entry_id, &parse_tag_names(&tags), TagSource::User)?;
+    attach_tags(&tx, entry_id, &analysis.ai_tags, TagSource::Ai)?;
+    tx.commit()?;
+
+    Ok(entry_id)
+}
+
+/// The comma-joined `ai_tags` column value; `general` when the analyzer found no topics.
+fn ai_tags_string(ai_tags: &[String]) -> String {
+    if ai_tags.is_empty() {
+        "general".to_string()
+    } else {
+        ai_tags.join(",")
+    }
 }
 
 pub fn read_journal_entry(conn: &Connection, journal_entry_id: i64) -> Result<Option<JournalEntry>, DaoError> {
-    conn.query_row(
+    let entry = conn.query_row(
         "SELECT id, entry, date, tags, sentiment, ai_tags FROM journal_entries WHERE id = ?1",
         params![journal_entry_id],
-        |row| {
-            Ok(JournalEntry {
-                id: row.get(0)?,
-                entry: row.get(1)?,
-                date: row.get(2)?,
-                tags: row.get(3)?,
-                sentiment: row.get(4)?,
-                ai_tags: row.get(5)?,
-            })
-        },
+        journal_entry_from_row,
     )
-    .optional().map_err(DaoError::from)
+    .optional()?;
+
+    match entry {
+        Some(mut entry) => {
+            entry.tag_list = get_tags_for_entry(conn, entry.id)?;
+            Ok(Some(entry))
+        }
+        None => Ok(None),
+    }
 }
 
-pub fn get_journal_entries_by_period(conn: &Connection, period: &str) -> Result<Vec<JournalEntry>, DaoError> {
-    let query = match period {
-        "week" => "SELECT id, entry, date, tags, sentiment, ai_tags FROM journal_entries WHERE date >= date('now', '-7 days') ORDER BY date DESC",
-        "month" => "SELECT id, entry, date, tags, sentiment, ai_tags FROM journal_entries WHERE date >= date('now', '-1 month') ORDER BY date DESC",
-        "year" => "SELECT id, entry, date, tags, sentiment, ai_tags FROM journal_entries WHERE date >= date('now', '-1 year') ORDER BY date DESC",
-        _ => return Err(DaoError::PreprocessingError(format!("Invalid period: {}", period))),
-    };
+/// Re-analyzes and saves the text and user tags of an existing journal entry. The entry
+/// keeps its id and date, so links to it from `snippets_used` are preserved.
+pub fn update_journal_entry(conn: &Connection, journal_entry: &JournalEntry) -> Result<(), DaoError> {
+    update_journal_entry_with_analyzer(conn, journal_entry, &KeywordAnalyzer)
+}
 
-    let mut stmt = conn.prepare(query)?;
-    let journal_iter = stmt.query_map([], |row| {
-        Ok(JournalEntry {
-            id: row.get(0)?,
-            entry: row.get(1)?,
-            date: row.get(2)?,
-            tags: row.get(3)?,
-            sentiment: row.get(4)?,
-            ai_tags: row.get(5)?,
-        })
-    })?;
+pub fn update_journal_entry_with_analyzer(
+    conn: &Connection,
+    journal_entry: &JournalEntry,
+    analyzer: &dyn JournalAnalyzer,
+) -> Result<(), DaoError> {
+    let tags = journal_entry.tags.clone().unwrap_or_default();
+    let analysis = analyzer.analyze(&journal_entry.entry)?;
+    let ai_tags = ai_tags_string(&analysis.ai_tags);
+
+    let tx = conn.unchecked_transaction()?;
+    let updated = tx.execute(
+        "UPDATE journal_entries SET entry = ?, tags = ?, sentiment = ?, ai_tags = ? WHERE id = ?",
+        params![&journal_entry.entry, tags, analysis.sentiment, ai_tags, journal_entry.id],
+    )?;
+    if updated == 0 {
+        return Err(DaoError::NotFound(format!("Journal entry {} does not exist", journal_entry.id)));
+    }
+
+    tx.execute("DELETE FROM entry_tags WHERE journal_entry_id = ?", params![journal_entry.id])?;
+    attach_tags(&tx, journal_entry.id, &parse_tag_names(&tags), TagSource::User)?;
+    attach_tags(&tx, journal_entry.id, &analysis.ai_tags, TagSource::Ai)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Deletes a journal entry together with its tag links and the `snippets_used` rows
+/// pointing at it.
+pub fn delete_journal_entry(conn: &Connection, journal_entry_id: i64) -> Result<(), DaoError> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM snippets_used WHERE journal_entry_id = ?", params![journal_entry_id])?;
+    tx.execute("DELETE FROM entry_tags WHERE journal_entry_id = ?", params![journal_entry_id])?;
+    let deleted = tx.execute("DELETE FROM journal_entries WHERE id = ?", params![journal_entry_id])?;
+    if deleted == 0 {
+        return Err(DaoError::NotFound(format!("Journal entry {} does not exist", journal_entry_id)));
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+const JOURNAL_LIST_FILTER: &str = "WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date < ?2)
+    AND (?3 IS NULL OR id IN (
+        SELECT et.journal_entry_id FROM entry_tags et JOIN tags t ON t.id = et.tag_id WHERE t.name = ?3))";
+
+/// One page of journal entries within `range`, optionally restricted to an exact tag,
+/// newest first.
+pub fn list_journal_entries(
+    conn: &Connection,
+    range: &DateRange,
+    tag: Option<&str>,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<JournalEntry>, DaoError> {
+    let (start, end) = range.sql_bounds();
+    let tag = tag.map(normalize_tag_name);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, entry, date, tags, sentiment, ai_tags FROM journal_entries {}
+         ORDER BY date DESC, id DESC LIMIT ?4 OFFSET ?5",
+        JOURNAL_LIST_FILTER
+    ))?;
+    let journal_iter = stmt.query_map(
+        params![start, end, tag, limit as i64, offset as i64],
+        journal_entry_from_row,
+    )?;
+
+    let mut entries = Vec::new();
+    for entry in journal_iter {
+        let mut entry = entry?;
+        entry.tag_list = get_tags_for_entry(conn, entry.id)?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Number of journal entries `list_journal_entries` can page through with the same filters.
+pub fn count_journal_entries(conn: &Connection, range: &DateRange, tag: Option<&str>) -> Result<usize, DaoError> {
+    let (start, end) = range.sql_bounds();
+    let tag = tag.map(normalize_tag_name);
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM journal_entries {}", JOURNAL_LIST_FILTER),
+        params![start, end, tag],
+        |row| row.get(0),
+    )
+    .map_err(DaoError::from)
+}
+
+/// Journal entries dated within `range`, newest first.
+pub fn get_journal_entries_in_range(conn: &Connection, range: &DateRange) -> Result<Vec<JournalEntry>, DaoError> {
+    let (start, end) = range.sql_bounds();
+    let mut stmt = conn.prepare(
+        "SELECT id, entry, date, tags, sentiment, ai_tags FROM journal_entries
+         WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date < ?2)
+         ORDER BY date DESC",
+    )?;
+    let journal_iter = stmt.query_map(params![start, end], journal_entry_from_row)?;
 
     let mut entries = Vec::new();
     for entry in journal_iter {
-        entries.push(entry?);
+        let mut entry = entry?;
+        entry.tag_list = get_tags_for_entry(conn, entry.id)?;
+        entries.push(entry);
     }
     Ok(entries)
 }
 
+/// Journal entries carrying the exact tag `tag_name`, from either source, newest first.
+pub fn get_journal_entries_by_tag(conn: &Connection, tag_name: &str) -> Result<Vec<JournalEntry>, DaoError> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT j.id, j.entry, j.date, j.tags, j.sentiment, j.ai_tags
+         FROM journal_entries j
+         JOIN entry_tags et ON et.journal_entry_id = j.id
+         JOIN tags t ON t.id = et.tag_id
+         WHERE t.name = ?1
+         ORDER BY j.date DESC",
+    )?;
+    let journal_iter = stmt.query_map(params![normalize_tag_name(tag_name)], journal_entry_from_row)?;
+
+    let mut entries = Vec::new();
+    for entry in journal_iter {
+        let mut entry = entry?;
+        entry.tag_list = get_tags_for_entry(conn, entry.id)?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Searches journal entries with SQLite FTS5, most relevant first.
+///
+/// `query` supports the FTS5 query syntax (`"exact phrase"`, `rust NOT async`, `borr*`).
+/// Text that is not a valid FTS5 query, such as a plain question, falls back to
+/// matching any of its words.
 pub fn search_journal_entries(conn: &Connection, query: &str) -> Result<Vec<JournalEntry>, DaoError> {
-    let search_query = format!("%{}%", query.to_lowercase());
-    
+    let results = search_journal_entries_ranked(conn, query, None, ("", ""))?;
+    Ok(results.into_iter().map(|result| result.entry).collect())
+}
+
+/// Like `search_journal_entries`, but also returns the BM25 rank and a snippet of
+/// the matching text with matches wrapped in `highlight` (opening, closing) markers.
+pub fn search_journal_entries_ranked(
+    conn: &Connection,
+    query: &str,
+    limit: Option<usize>,
+    highlight: (&str, &str),
+) -> Result<Vec<JournalSearchResult>, DaoError> {
+    match run_journal_fts_query(conn, query, limit, highlight) {
+        Err(DaoError::DatabaseError(err)) if is_fts_syntax_error(&err) => {
+            match any_word_fts_query(query) {
+                Some(fallback) => run_journal_fts_query(conn, &fallback, limit, highlight),
+                None => Ok(Vec::new()),
+            }
+        }
+        result => result,
+    }
+}
+
+fn run_journal_fts_query(
+    conn: &Connection,
+    fts_query: &str,
+    limit: Option<usize>,
+    highlight: (&str, &str),
+) -> Result<Vec<JournalSearchResult>, DaoError> {
     let mut stmt = conn.prepare(
-        "SELECT id, entry, date, tags, sentiment, ai_tags FROM journal_entries 
-         WHERE LOWER(entry) LIKE ?1 OR LOWER(tags) LIKE ?1 OR LOWER(ai_tags) LIKE ?1 
-         ORDER BY date DESC"
+        "SELECT j.id, j.entry, j.date, j.tags, j.sentiment, j.ai_tags,
+                snippet(journal_fts, -1, ?2, ?3, '…', 16), journal_fts.rank
+         FROM journal_fts JOIN journal_entries j ON j.id = journal_fts.rowid
+         WHERE journal_fts MATCH ?1
+         ORDER BY journal_fts.rank, j.date DESC
+         LIMIT ?4",
     )?;
-    
-    let journal_iter = stmt.query_map([search_query], |row| {
-        Ok(JournalEntry {
-            id: row.get(0)?,
-            entry: row.get(1)?,
-            date: row.get(2)?,
-            tags: row.get(3)?,
-            sentiment: row.get(4)?,
-            ai_tags: row.get(5)?,
+
+    let limit = limit.map_or(-1, |l| l as i64);
+    let results = stmt.query_map(params![fts_query, highlight.0, highlight.1, limit], |row| {
+        Ok(JournalSearchResult {
+            entry: journal_entry_from_row(row)?,
+            snippet: row.get(6)?,
+            rank: row.get(7)?,
         })
     })?;
 
     let mut entries = Vec::new();
-    for entry in journal_iter {
-        entries.push(entry?);
+    for result in results {
+        let mut result = result?;
+        result.entry.tag_list = get_tags_for_entry(conn, result.entry.id)?;
+        entries.push(result);
     }
     Ok(entries)
 }
 
+fn is_fts_syntax_error(err: &rusqlite::Error) -> bool {
+    let message = err.to_string();
+    message.contains("fts5: syntax error") || message.contains("no such column")
+}
+
+/// Turns free text into an FTS5 query matching any of its words, e.g.
+/// `How do I feel about coding?` becomes `"How" OR "do" OR ... OR "coding"`.
+fn any_word_fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"", word))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" OR "))
+    }
+}
+
+fn journal_entry_from_row(row: &Row) -> rusqlite::Result<JournalEntry> {
+    Ok(JournalEntry {
+        id: row.get(0)?,
+        entry: row.get(1)?,
+        date: row.get(2)?,
+        tags: row.get(3)?,
+        sentiment: row.get(4)?,
+        ai_tags: row.get(5)?,
+        tag_list: Vec::new(),
+    })
+}
+
+// Tags
+
+/// Tag names are stored trimmed and lowercased so `Rust` and ` rust` are the same tag.
+pub fn normalize_tag_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Splits a comma-separated tag string into unique, normalized tag names.
+pub fn parse_tag_names(tags: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for name in tags.split(',').map(normalize_tag_name) {
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn get_or_create_tag(conn: &Connection, name: &str) -> Result<i64, DaoError> {
+    conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![name])?;
+    conn.query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| row.get(0))
+        .map_err(DaoError::from)
+}
+
+fn attach_tags(conn: &Connection, entry_id: i64, names: &[String], source: TagSource) -> Result<(), DaoError> {
+    for name in names {
+        let name = normalize_tag_name(name);
+        if name.is_empty() {
+            continue;
+        }
+        let tag_id = get_or_create_tag(conn, &name)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO entry_tags (journal_entry_id, tag_id, source) VALUES (?1, ?2, ?3)",
+            params![entry_id, tag_id, source.as_str()],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn get_tags_for_entry(conn: &Connection, journal_entry_id: i64) -> Result<Vec<Tag>, DaoError> {
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.name, et.source FROM entry_tags et JOIN tags t ON t.id = et.tag_id
+         WHERE et.journal_entry_id = ?1 ORDER BY et.source DESC, t.name",
+    )?;
+    let tag_iter = stmt.query_map(params![journal_entry_id], |row| {
+        let source: String = row.get(2)?;
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            source: TagSource::parse(&source).unwrap_or(TagSource::User),
+        })
+    })?;
+
+    let mut tags = Vec::new();
+    for tag in tag_iter {
+        tags.push(tag?);
+    }
+    Ok(tags)
+}
+
+/// All tags with their usage counts, most used first.
+pub fn list_tags(conn: &Connection) -> Result<Vec<TagUsage>, DaoError> {
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.name,
+                COALESCE(SUM(CASE WHEN et.source = 'user' THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN et.source = 'ai' THEN 1 ELSE 0 END), 0)
+         FROM tags t LEFT JOIN entry_tags et ON et.tag_id = t.id
+         GROUP BY t.id
+         ORDER BY COUNT(et.tag_id) DESC, t.name",
+    )?;
+    let usage_iter = stmt.query_map([], |row| {
+        Ok(TagUsage {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            user_count: row.get(2)?,
+            ai_count: row.get(3)?,
+        })
+    })?;
+
+    let mut tags = Vec::new();
+    for usage in usage_iter {
+        tags.push(usage?);
+    }
+    Ok(tags)
+}
+
+/// Number of journal entries carrying each tag, most used first. An entry tagged
+/// both by the user and the AI is counted once. `source` restricts the count to one origin.
+pub fn count_tags(conn: &Connection, source: Option<TagSource>) -> Result<Vec<(String, usize)>, DaoError> {
+    let mut stmt = conn.prepare(
+        "SELECT t.name, COUNT(DISTINCT et.journal_entry_id) AS uses
+         FROM entry_tags et JOIN tags t ON t.id = et.tag_id
+         WHERE ?1 IS NULL OR et.source = ?1
+         GROUP BY t.id
+         ORDER BY uses DESC, t.name",
+    )?;
+    let count_iter = stmt.query_map(params![source.map(|s| s.as_str())], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+
+    let mut counts = Vec::new();
+    for count in count_iter {
+        counts.push(count?);
+    }
+    Ok(counts)
+}
+
+fn get_tag_id(conn: &Connection, name: &str) -> Result<i64, DaoError> {
+    conn.query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| DaoError::NotFound(format!("Tag '{}' does not exist", name)))
+}
+
+/// Renames a tag. Fails if a tag with the new name already exists; use `merge_tags` for that.
+pub fn rename_tag(conn: &Connection, old_name: &str, new_name: &str) -> Result<(), DaoError> {
+    let old_name = normalize_tag_name(old_name);
+    let new_name = normalize_tag_name(new_name);
+    if new_name.is_empty() {
+        return Err(DaoError::InvalidInput("Tag name cannot be empty".to_string()));
+    }
+    let tag_id = get_tag_id(conn, &old_name)?;
+    if old_name == new_name {
+        return Ok(());
+    }
+    let existing: Option<i64> = conn
+        .query_row("SELECT id FROM tags WHERE name = ?1", params![new_name], |row| row.get(0))
+        .optional()?;
+    if existing.is_some() {
+        return Err(DaoError::InvalidInput(format!(
+            "Tag '{}' already exists, merge the tags instead",
+            new_name
+        )));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("UPDATE tags SET name = ?1 WHERE id = ?2", params![new_name, tag_id])?;
+    refresh_tag_strings_for_tag(&tx, tag_id)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Moves every use of the `sources` tags onto `target` (created if needed) and removes the sources.
+pub fn merge_tags(conn: &Connection, sources: &[&str], target: &str) -> Result<(), DaoError> {
+    let target = normalize_tag_name(target);
+    if target.is_empty() {
+        return Err(DaoError::InvalidInput("Tag name cannot be empty".to_string()));
+    }
 
+    let tx = conn.unchecked_transaction()?;
+    let target_id = get_or_create_tag(&tx, &target)?;
+    for source in sources {
+        let source = normalize_tag_name(source);
+        if source == target {
+            continue;
+        }
+        let source_id = get_tag_id(&tx, &source)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO entry_tags (journal_entry_id, tag_id, source)
+             SELECT journal_entry_id, ?1, source FROM entry_tags WHERE tag_id = ?2",
+            params![target_id, source_id],
+        )?;
+        tx.execute("DELETE FROM entry_tags WHERE tag_id = ?1", params![source_id])?;
+        tx.execute("DELETE FROM tags WHERE id = ?1", params![source_id])?;
+    }
+    refresh_tag_strings_for_tag(&tx, target_id)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Rewrites the denormalized `tags` / `ai_tags` strings of every entry using `tag_id`,
+/// so they (and the search index built from them) follow renames and merges.
+fn refresh_tag_strings_for_tag(conn: &Connection, tag_id: i64) -> Result<(), DaoError> {
+    conn.execute(
+        "UPDATE journal_entries SET
+            tags = (SELECT group_concat(name, ', ') FROM (
+                SELECT t.name FROM entry_tags et JOIN tags t ON t.id = et.tag_id
+                WHERE et.journal_entry_id = journal_entries.id AND et.source = 'user' ORDER BY t.name)),
+            ai_tags = COALESCE((SELECT group_concat(name, ',') FROM (
+                SELECT t.name FROM entry_tags et JOIN tags t ON t.id = et.tag_id
+                WHERE et.journal_entry_id = journal_entries.id AND et.source = 'ai' ORDER BY t.name)), 'general')
+         WHERE id IN (SELECT journal_entry_id FROM entry_tags WHERE tag_id = ?1)",
+        params![tag_id],
+    )?;
+    Ok(())
+}
 
-use std::collections::HashSet;
+
+
+use std::collections::{HashMap, HashSet};
 
 pub fn summarize_journal_entries(entries: &Vec<JournalEntry>) -> JournalSummary {
     let mut positive_count = 0;
     let mut negative_count = 0;
     let mut neutral_count = 0;
-    let mut all_ai_tags = Vec::new();
+    let mut topic_counts: HashMap<String, usize> = HashMap::new();
 
     for entry in entries {
         match entry.sentiment.as_deref() {
@@ -292,17 +1048,14 @@ pub fn summarize_journal_entries(entries: &Vec<JournalEntry>) -> JournalSummary
             _ => neutral_count += 1,
         }
 
-        if let Some(ai_tags) = &entry.ai_tags {
-            all_ai_tags.extend(ai_tags.split(',').map(|s| s.trim()));
+        // Count each topic once per entry, whether the user or the AI tagged it
+        for topic in entry_topics(entry) {
+            *topic_counts.entry(topic).or_insert(0) += 1;
         }
     }
 
-    let common_topics = all_ai_tags.into_iter()
-        .collect::<HashSet<_>>()
-        .into_iter()
-        .take(5)
-        .collect::<Vec<_>>()
-        .join(", ");
+    let mut common_topics: Vec<(String, usize)> = topic_counts.into_iter().collect();
+    common_topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
     JournalSummary {
         total_entries: entries.len(),
@@ -313,13 +1066,29 @@ pub fn summarize_journal_entries(entries: &Vec<JournalEntry>) -> JournalSummary
     }
 }
 
-pub fn generate_summary_for_period(conn: &Connection, period: &str) -> Result<JournalSummary, DaoError> {
-    let date_filter = match period {
-        "week" => "WHERE date >= date('now', '-7 days')",
-        "month" => "WHERE date >= date('now', '-1 month')",
-        "year" => "WHERE date >= date('now', '-1 year')",
-        _ => return Err(DaoError::PreprocessingError(format!("Invalid period: {}", period))),
-    };
+/// Distinct topics of an entry. Uses the normalized tags when they were loaded and
+/// falls back to the tag strings otherwise, skipping the analyzer's `general` placeholder.
+fn entry_topics(entry: &JournalEntry) -> HashSet<String> {
+    if !entry.tag_list.is_empty() {
+        return entry.tag_list.iter().map(|tag| tag.name.clone()).collect();
+    }
+
+    let mut topics: HashSet<String> = entry
+        .tags
+        .as_deref()
+        .map(parse_tag_names)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    if let Some(ai_tags) = entry.ai_tags.as_deref() {
+        topics.extend(parse_tag_names(ai_tags).into_iter().filter(|tag| tag != "general"));
+    }
+    topics
+}
+
+pub fn generate_summary_for_range(conn: &Connection, range: &DateRange) -> Result<JournalSummary, DaoError> {
+    let (start, end) = range.sql_bounds();
+    let date_filter = "WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date < ?2)";
 
     let query = format!(
         "SELECT
@@ -333,17 +1102,27 @@ pub fn generate_summary_for_period(conn: &Connection, period: &str) -> Result<Jo
 
     let (positive_count, negative_count, neutral_count, total_entries) = conn.query_row(
         &query,
-        [],
+        params![start, end],
         |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
     )?;
 
-    // NOTE: Topic aggregation is complex in a single SQL query with SQLite.
-    // We will leave this part for a future task. For now, it returns a placeholder.
-    let common_topics = if total_entries > 0 {
-        "Topics require separate logic".to_string()
-    } else {
-        String::new()
-    };
+    let topic_query = format!(
+        "SELECT t.name, COUNT(DISTINCT et.journal_entry_id) AS uses
+        FROM entry_tags et
+        JOIN tags t ON t.id = et.tag_id
+        JOIN journal_entries ON journal_entries.id = et.journal_entry_id
+        {}
+        GROUP BY t.id
+        ORDER BY uses DESC, t.name",
+        date_filter
+    );
+    let mut stmt = conn.prepare(&topic_query)?;
+    let topic_iter = stmt.query_map(params![start, end], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    let mut common_topics = Vec::new();
+    for topic in topic_iter {
+        common_topics.push(topic?);
+    }
 
     Ok(JournalSummary {
         total_entries,
@@ -356,66 +1135,555 @@ pub fn generate_summary_for_period(conn: &Connection, period: &str) -> Result<Jo
 
 
 
+// Learning notes
+
+/// Registers the markdown file at `file_path` as a learning note and indexes its
+/// content. The path is stored canonicalized, so the note can be found from anywhere.
+/// A file that is already a note is not added again: its note is re-indexed if the
+/// file changed, and its ID is returned.
 pub fn create_learning_note(conn: &Connection, file_path: &str) -> Result<i64, DaoError> {
-    let path = Path::new(file_path);
+    let path = std::fs::canonicalize(file_path).map_err(|e| note_file_error(file_path, e))?;
+    let content = read_note_file(&path)?;
+    if let Some(note) = find_learning_note_by_path(conn, &path.to_string_lossy())? {
+        refresh_learning_note(conn, &note, &path, &content)?;
+        return Ok(note.id);
+    }
     let file_name = match path.file_name() {
         Some(name) => name.to_str().unwrap_or("unknown_file"),
         None => "unknown_file",
     };
 
-    conn.execute(
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
         "INSERT INTO learning_notes (file_path, file_name) VALUES (?, ?)",
-        params![file_path, file_name],
+        params![path.to_string_lossy(), file_name],
     )?;
-    Ok(conn.last_insert_rowid())
+    let note_id = tx.last_insert_rowid();
+    index_learning_note(&tx, note_id, &content)?;
+    tx.commit()?;
+    Ok(note_id)
+}
+
+fn read_note_file(path: &Path) -> Result<String, DaoError> {
+    std::fs::read_to_string(path).map_err(|e| note_file_error(&path.to_string_lossy(), e))
+}
+
+fn note_file_error(file_path: &str, error: std::io::Error) -> DaoError {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => DaoError::NotFound(format!("Note file '{}' does not exist", file_path)),
+        _ => DaoError::InvalidInput(format!("Cannot read note file '{}': {}", file_path, error)),
+    }
+}
+
+/// Registers the file at `file_path` unless it is already a note, either under the same
+/// canonical path or as another file with identical content. Notes whose file changed
+/// since they were indexed are re-indexed.
+pub fn import_learning_note(conn: &Connection, file_path: &Path) -> Result<NoteImport, DaoError> {
+    let path = std::fs::canonicalize(file_path).map_err(|e| note_file_error(&file_path.to_string_lossy(), e))?;
+    let content = read_note_file(&path)?;
+
+    if let Some(note) = find_learning_note_by_path(conn, &path.to_string_lossy())? {
+        if refresh_learning_note(conn, &note, &path, &content)? {
+            return Ok(NoteImport::Changed(note.id));
+        }
+        return Ok(NoteImport::Unchanged(note.id));
+    }
+    if let Some(note) = find_learning_note_by_hash(conn, &markdown::content_hash(&content))? {
+        return Ok(NoteImport::Duplicate(note.id));
+    }
+    create_learning_note(conn, &path.to_string_lossy()).map(NoteImport::Added)
+}
+
+/// Re-indexes `note` if its file's `content` changed since it was last indexed, and
+/// indexes its links if they never were. A note stored under another spelling of `path`
+/// is moved to it. Returns whether the note changed.
+fn refresh_learning_note(conn: &Connection, note: &LearningNote, path: &Path, content: &str) -> Result<bool, DaoError> {
+    let tx = conn.unchecked_transaction()?;
+    if Path::new(&note.file_path) != path {
+        relink_learning_note(&tx, note.id, path)?;
+    }
+    let changed = note.content_hash.as_deref() != Some(markdown::content_hash(content).as_str());
+    if changed {
+        index_learning_note(&tx, note.id, content)?;
+    } else {
+        index_pending_note_links(&tx, note.id, content)?;
+    }
+    tx.commit()?;
+    Ok(changed)
+}
+
+/// The note of the file at `file_path`, a canonical path. Notes added before paths were
+/// canonicalized may be stored relative to the directory `note add` ran in; they match
+/// when their path leads to the same file from the current directory.
+pub fn find_learning_note_by_path(conn: &Connection, file_path: &str) -> Result<Option<LearningNote>, DaoError> {
+    let note = conn
+        .query_row(
+            &format!("SELECT {} FROM learning_notes WHERE file_path = ?1", NOTE_COLUMNS),
+            params![file_path],
+            learning_note_from_row,
+        )
+        .optional()?;
+    if note.is_some() {
+        return Ok(note);
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM learning_notes WHERE file_path NOT LIKE '/%' ORDER BY id",
+        NOTE_COLUMNS
+    ))?;
+    let notes = stmt.query_map([], learning_note_from_row)?;
+    for note in notes {
+        let note = note?;
+        let path = Path::new(&note.file_path);
+        if path.is_relative() && std::fs::canonicalize(path).is_ok_and(|path| path == Path::new(file_path)) {
+            return Ok(Some(note));
+        }
+    }
+    Ok(None)
+}
+
+/// The oldest note whose file had the content with this hash when it was last indexed.
+pub fn find_learning_note_by_hash(conn: &Connection, content_hash: &str) -> Result<Option<LearningNote>, DaoError> {
+    conn.query_row(
+        &format!("SELECT {} FROM learning_notes WHERE content_hash = ?1 ORDER BY id LIMIT 1", NOTE_COLUMNS),
+        params![content_hash],
+        learning_note_from_row,
+    )
+    .optional()
+    .map_err(DaoError::from)
+}
+
+pub fn list_learning_notes(conn: &Connection) -> Result<Vec<LearningNote>, DaoError> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM learning_notes ORDER BY id", NOTE_COLUMNS))?;
+    let notes = stmt.query_map([], learning_note_from_row)?;
+    notes.collect::<Result<_, _>>().map_err(DaoError::from)
+}
+
+/// Notes whose file changed since it was last indexed, or was never indexed. Files that
+/// cannot be read are listed too, as they cannot be checked; `note sync` reports why.
+/// Missing files are not stale; `note sync` flags them instead.
+pub fn list_stale_learning_notes(conn: &Connection) -> Result<Vec<LearningNote>, DaoError> {
+    let mut stale = Vec::new();
+    for note in list_learning_notes(conn)? {
+        let path = Path::new(&note.file_path);
+        if !path.is_file() {
+            continue;
+        }
+        let hash = read_note_file(path).ok().map(|content| markdown::content_hash(&content));
+        if hash.is_none() || note.content_hash != hash {
+            stale.push(note);
+        }
+    }
+    Ok(stale)
+}
+
+/// Remembers a directory added with `note scan`, so `note sync` can look for moved
+/// notes in it. Scanning the same directory again updates its glob and excludes.
+pub fn record_note_root(conn: &Connection, path: &Path, glob: &str, excludes: &[String]) -> Result<(), DaoError> {
+    let path = std::fs::canonicalize(path).map_err(|e| note_file_error(&path.to_string_lossy(), e))?;
+    let excludes = serde_json::to_string(excludes).expect("excludes serialize to JSON");
+    conn.execute(
+        "INSERT INTO note_roots (path, glob, excludes) VALUES (?1, ?2, ?3)
+         ON CONFLICT (path) DO UPDATE SET glob = excluded.glob, excludes = excluded.excludes,
+                                          scanned_at = CURRENT_TIMESTAMP",
+        params![path.to_string_lossy(), glob, excludes],
+    )?;
+    Ok(())
+}
+
+pub fn list_note_roots(conn: &Connection) -> Result<Vec<NoteRoot>, DaoError> {
+    let mut stmt = conn.prepare("SELECT id, path, glob, excludes, scanned_at FROM note_roots ORDER BY path")?;
+    let roots = stmt.query_map([], |row| {
+        let excludes: String = row.get(3)?;
+        Ok(NoteRoot {
+            id: row.get(0)?,
+            path: row.get(1)?,
+            glob: row.get(2)?,
+            excludes: serde_json::from_str(&excludes)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?,
+            scanned_at: row.get(4)?,
+        })
+    })?;
+    roots.collect::<Result<_, _>>().map_err(DaoError::from)
+}
+
+/// Checks every note's file: changed files are re-indexed, and missing ones are looked
+/// for by content under the scanned directories, then relinked or flagged missing.
+/// Files that cannot be read are reported and left as they are, and so are notes stored
+/// with a relative path (before paths were canonicalized) that leads nowhere from here;
+/// the ones that do lead to their file are stored canonicalized from now on.
+/// Returns each note as it is after the sync, with what was done to it.
+pub fn sync_learning_notes(conn: &Connection) -> Result<Vec<(LearningNote, NoteSync)>, DaoError> {
+    let tx = conn.unchecked_transaction()?;
+    let mut results = Vec::new();
+    let mut missing = Vec::new();
+    for mut note in list_learning_notes(&tx)? {
+        if Path::new(&note.file_path).is_relative() {
+            match std::fs::canonicalize(&note.file_path) {
+                Ok(path) if path.is_file() => {
+                    relink_learning_note(&tx, note.id, &path)?;
+                    note.file_path = path.to_string_lossy().into_owned();
+                }
+                _ => {
+                    results.push((note.id, NoteSync::Unresolved));
+                    continue;
+                }
+            }
+        }
+        let path = Path::new(&note.file_path);
+        if !path.is_file() {
+            missing.push(note);
+            continue;
+        }
+        let content = match read_note_file(path) {
+            Ok(content) => content,
+            Err(DaoError::InvalidInput(message)) => {
+                results.push((note.id, NoteSync::Unreadable(message)));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let changed = note.content_hash.as_deref() != Some(markdown::content_hash(&content).as_str());
+        if changed {
+            index_learning_note(&tx, note.id, &content)?;
+        } else {
+            index_pending_note_links(&tx, note.id, &content)?;
+        }
+        let sync = if note.missing_since.is_some() {
+            tx.execute("UPDATE learning_notes SET missing_since = NULL WHERE id = ?1", params![note.id])?;
+            NoteSync::Restored
+        } else if changed {
+            NoteSync::Updated
+        } else {
+            NoteSync::Unchanged
+        };
+        results.push((note.id, sync));
+    }
+
+    if !missing.is_empty() {
+        let mut moved_files = unregistered_files_by_hash(&tx)?;
+        for note in missing {
+            let moved_to = note.content_hash.as_ref().and_then(|hash| moved_files.remove(hash));
+            let sync = match moved_to {
+                Some(path) => {
+                    relink_learning_note(&tx, note.id, &path)?;
+                    NoteSync::Moved(note.file_path)
+                }
+                None => {
+                    mark_learning_note_missing(&tx, note.id)?;
+                    NoteSync::Missing
+                }
+            };
+            results.push((note.id, sync));
+        }
+    }
+
+    let mut synced = Vec::new();
+    for (note_id, sync) in results {
+        let note = read_learning_note(&tx, note_id)?.ok_or_else(|| DaoError::NotFound(format!("Note #{}", note_id)))?;
+        synced.push((note, sync));
+    }
+    synced.sort_by_key(|(note, _)| note.id);
+    tx.commit()?;
+    Ok(synced)
+}
+
+/// Points a note at the new path of its moved file.
+pub fn relink_learning_note(conn: &Connection, note_id: i64, file_path: &Path) -> Result<(), DaoError> {
+    let file_name = file_path.file_name().and_then(|name| name.to_str()).unwrap_or("unknown_file");
+    conn.execute(
+        "UPDATE learning_notes SET file_path = ?1, file_name = ?2, missing_since = NULL WHERE id = ?3",
+        params![file_path.to_string_lossy(), file_name, note_id],
+    )?;
+    Ok(())
 }
 
+/// Flags a note whose file is gone. A note that is already flagged keeps its date.
+pub fn mark_learning_note_missing(conn: &Connection, note_id: i64) -> Result<(), DaoError> {
+    conn.execute(
+        "UPDATE learning_notes SET missing_since = COALESCE(missing_since, CURRENT_TIMESTAMP) WHERE id = ?1",
+        params![note_id],
+    )?;
+    Ok(())
+}
 
+/// Files under the scanned directories that are not notes yet, by content hash.
+fn unregistered_files_by_hash(conn: &Connection) -> Result<HashMap<String, PathBuf>, DaoError> {
+    let mut files = HashMap::new();
+    for root in list_note_roots(conn)? {
+        // Roots that were deleted or no longer parse have nothing to relink to
+        let Ok(found) = scan::find_files(Path::new(&root.path), &root.glob, &root.excludes) else {
+            continue;
+        };
+        for file in found.files {
+            let Ok(file) = std::fs::canonicalize(&file) else {
+                continue;
+            };
+            if find_learning_note_by_path(conn, &file.to_string_lossy())?.is_some() {
+                continue;
+            }
+            if let Ok(content) = std::fs::read_to_string(&file) {
+                files.entry(markdown::content_hash(&content)).or_insert(file);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Stores the title, hash, word count and headings of a note's markdown and
+/// replaces its full-text index entry.
+fn index_learning_note(conn: &Connection, note_id: i64, content: &str) -> Result<(), DaoError> {
+    let summary = markdown::summarize(content);
+    let headings = serde_json::to_string(&summary.headings).expect("headings serialize to JSON");
+    conn.execute(
+        "UPDATE learning_notes SET title = ?1, content_hash = ?2, word_count = ?3, headings = ?4,
+                updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?5",
+        params![summary.title, summary.content_hash, summary.word_count as i64, headings, note_id],
+    )?;
+    let heading_text: Vec<&str> = summary.headings.iter().map(|heading| heading.text.as_str()).collect();
+    conn.execute("DELETE FROM notes_fts WHERE rowid = ?1", params![note_id])?;
+    conn.execute(
+        "INSERT INTO notes_fts (rowid, title, headings, body) VALUES (?1, ?2, ?3, ?4)",
+        params![note_id, summary.title, heading_text.join("\n"), content],
+    )?;
+    index_note_links(conn, note_id, &summary.links)
+}
 
+/// Indexes the links of a note that was indexed before links were, from its unchanged content.
+fn index_pending_note_links(conn: &Connection, note_id: i64, content: &str) -> Result<(), DaoError> {
+    let pending: bool = conn.query_row(
+        "SELECT links_indexed_at IS NULL FROM learning_notes WHERE id = ?1",
+        params![note_id],
+        |row| row.get(0),
+    )?;
+    if pending {
+        index_note_links(conn, note_id, &markdown::summarize(content).links)?;
+    }
+    Ok(())
+}
 
+fn index_note_links(conn: &Connection, note_id: i64, links: &[markdown::LinkRef]) -> Result<(), DaoError> {
+    conn.execute("DELETE FROM note_links WHERE source_note_id = ?1", params![note_id])?;
+    for link in links {
+        conn.execute(
+            "INSERT INTO note_links (source_note_id, kind, target) VALUES (?1, ?2, ?3)",
+            params![note_id, link.kind.as_str(), link.target],
+        )?;
+    }
+    conn.execute(
+        "UPDATE learning_notes SET links_indexed_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        params![note_id],
+    )?;
+    Ok(())
+}
 
+/// Every link between learning notes, in order of the linking note. Links are resolved
+/// against the notes' current files: markdown links by path relative to the linking
+/// note, wikilinks by file name (preferring the linking note's directory), then by title.
+pub fn list_note_links(conn: &Connection) -> Result<Vec<NoteLink>, DaoError> {
+    let notes = list_learning_notes(conn)?;
+    let mut stmt = conn.prepare("SELECT source_note_id, kind, target FROM note_links ORDER BY source_note_id, id")?;
+    let rows = stmt.query_map([], |row| {
+        let kind: String = row.get(1)?;
+        let kind = match kind.as_str() {
+            "wiki" => NoteLinkKind::Wiki,
+            _ => NoteLinkKind::Markdown,
+        };
+        Ok((row.get::<_, i64>(0)?, kind, row.get::<_, String>(2)?))
+    })?;
 
+    let mut links = Vec::new();
+    for row in rows {
+        let (source_note_id, kind, target) = row?;
+        let target_note_id = notes
+            .iter()
+            .find(|note| note.id == source_note_id)
+            .and_then(|source| resolve_note_link(source, kind, &target, &notes));
+        links.push(NoteLink {
+            source_note_id,
+            kind,
+            target,
+            target_note_id,
+        });
+    }
+    Ok(links)
+}
 
+fn resolve_note_link(source: &LearningNote, kind: NoteLinkKind, target: &str, notes: &[LearningNote]) -> Option<i64> {
+    let source_dir = Path::new(&source.file_path).parent()?;
+    match kind {
+        NoteLinkKind::Markdown => {
+            let path = normalize_path(&source_dir.join(target));
+            notes.iter().find(|note| Path::new(&note.file_path) == path).map(|note| note.id)
+        }
+        NoteLinkKind::Wiki => {
+            // `[[dir/page]]` names the end of a path, `[[page]]` just the file
+            let page = Path::new(target);
+            let by_path = |note: &&LearningNote| {
+                let path = Path::new(&note.file_path).with_extension("");
+                path.ends_with(page)
+                    || path.file_name().is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(target))
+            };
+            notes
+                .iter()
+                .filter(by_path)
+                .min_by_key(|note| Path::new(&note.file_path).parent() != Some(source_dir))
+                .or_else(|| {
+                    notes.iter().find(|note| {
+                        note.title.as_deref().is_some_and(|title| title.eq_ignore_ascii_case(target))
+                    })
+                })
+                .map(|note| note.id)
+        }
+    }
+}
 
-#[cfg(test)]
-mod tests {
-    use super::*; // This imports everything from the parent module (dao.rs)
+/// Resolves `.` and `..` without touching the filesystem, as linked files may not exist.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
 
-    #[test]
-    fn test_preprocess_code() {
-        let raw_code = r#"console.log("Hello, it's me!");"#;
-        let processed_code = preprocess_code(raw_code, "js").unwrap();
-        assert_eq!(
-            processed_code,
-            "'''\nconsole.log(\"Hello, it''s me!\");\n'''"
-        );
+/// Searches the indexed content of learning notes with an FTS5 query, best matches first.
+/// Like journal search, queries that are not valid FTS5 syntax match any of their words.
+pub fn search_learning_notes(
+    conn: &Connection,
+    query: &str,
+    limit: Option<usize>,
+    highlight: (&str, &str),
+) -> Result<Vec<NoteSearchResult>, DaoError> {
+    match run_notes_fts_query(conn, query, limit, highlight) {
+        Err(DaoError::DatabaseError(err)) if is_fts_syntax_error(&err) => match any_word_fts_query(query) {
+            Some(fallback) => run_notes_fts_query(conn, &fallback, limit, highlight),
+            None => Ok(Vec::new()),
+        },
+        result => result,
     }
+}
 
-    #[test]
-    fn test_rust_preprocess_code() {
-        let raw_code = r#"fn main() {
-    // Path to the SQLite database file
-    let db_path = "../clidblocal.db";
+fn run_notes_fts_query(
+    conn: &Connection,
+    fts_query: &str,
+    limit: Option<usize>,
+    highlight: (&str, &str),
+) -> Result<Vec<NoteSearchResult>, DaoError> {
+    let columns: Vec<String> = NOTE_COLUMNS.split(", ").map(|column| format!("n.{}", column)).collect();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, snippet(notes_fts, -1, ?2, ?3, '…', 16), notes_fts.rank
+         FROM notes_fts JOIN learning_notes n ON n.id = notes_fts.rowid
+         WHERE notes_fts MATCH ?1
+         ORDER BY notes_fts.rank, n.updated_at DESC
+         LIMIT ?4",
+        columns.join(", ")
+    ))?;
+
+    let limit = limit.map_or(-1, |l| l as i64);
+    let results = stmt.query_map(params![fts_query, highlight.0, highlight.1, limit], |row| {
+        Ok(NoteSearchResult {
+            note: learning_note_from_row(row)?,
+            snippet: row.get(NOTE_COLUMN_COUNT)?,
+            rank: row.get(NOTE_COLUMN_COUNT + 1)?,
+        })
+    })?;
+    results.collect::<Result<_, _>>().map_err(DaoError::from)
+}
 
-    // Create a new database connection
-    let database = db::Database::new(db_path).expect("Failed to connect to the database ");
+const NOTE_COLUMNS: &str =
+    "id, file_path, file_name, title, content_hash, word_count, headings, created_at, updated_at, missing_since";
+const NOTE_COLUMN_COUNT: usize = 10;
 
-    //Initialize the database ( create tables if they don't exist)
-    database.initialize().expect("Failed to initialize the database");
-}"#;
+fn learning_note_from_row(row: &Row) -> rusqlite::Result<LearningNote> {
+    let headings: Option<String> = row.get(6)?;
+    let headings = match headings {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e)))?,
+        None => Vec::new(),
+    };
+    Ok(LearningNote {
+        id: row.get(0)?,
+        file_path: row.get(1)?,
+        file_name: row.get(2)?,
+        title: row.get(3)?,
+        content_hash: row.get(4)?,
+        word_count: row.get(5)?,
+        headings,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+        missing_since: row.get(9)?,
+    })
+}
 
-        let processed_code = preprocess_code(raw_code, "rust").unwrap();
+// Database integrity
 
-        // Check that the code was properly formatted with triple quotes
-        assert!(processed_code.starts_with("'''\n"));
-        assert!(processed_code.ends_with("\n'''"));
+/// Language given to snippets whose language is missing and cannot be detected.
+const FALLBACK_LANGUAGE: &str = "text";
 
-        // Check that single quotes were escaped correctly
-        assert!(processed_code.contains("\"Failed to connect to the database \""));
-        assert!(processed_code.contains("\"Failed to initialize the database\""));
+/// Rows referencing parent rows that do not exist, e.g. links to deleted snippets left
+/// behind while foreign keys were not enforced.
+pub fn find_orphans(conn: &Connection) -> Result<Vec<OrphanRow>, DaoError> {
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let orphan_iter = stmt.query_map([], |row| {
+        Ok(OrphanRow {
+            table: row.get(0)?,
+            rowid: row.get(1)?,
+            parent: row.get(2)?,
+        })
+    })?;
+
+    let mut orphans: Vec<OrphanRow> = Vec::new();
+    for orphan in orphan_iter {
+        let orphan = orphan?;
+        // A row with several dangling references is reported once per reference
+        if !orphans.iter().any(|o| o.table == orphan.table && o.rowid == orphan.rowid) {
+            orphans.push(orphan);
+        }
+    }
+    Ok(orphans)
+}
 
-        // Check that newlines are preserved
-        assert!(processed_code.contains("Path to the SQLite database file"));
-        assert!(processed_code.contains("Create a new database connection"));
+/// Fixes every orphan row. Snippets with a missing language are kept and moved to their
+/// detected language (or `text`); every other orphan is a link or history row and is deleted.
+pub fn repair_orphans(conn: &Connection) -> Result<Vec<(OrphanRow, OrphanRepair)>, DaoError> {
+    let tx = conn.unchecked_transaction()?;
+    let mut repaired = Vec::new();
+    for orphan in find_orphans(&tx)? {
+        let repair = if orphan.table == "code_snippets" {
+            let code: String = tx.query_row(
+                "SELECT COALESCE(full_code, '') FROM code_snippets WHERE rowid = ?1",
+                params![orphan.rowid],
+                |row| row.get(0),
+            )?;
+            let language = match detect_snippet_language(&tx, &code, None)? {
+                Some(detection) => detection.language,
+                None => {
+                    if !language_exists(&tx, FALLBACK_LANGUAGE)? {
+                        create_language(&tx, FALLBACK_LANGUAGE)?;
+                    }
+                    FALLBACK_LANGUAGE.to_string()
+                }
+            };
+            tx.execute(
+                "UPDATE code_snippets SET language_id = ?1 WHERE rowid = ?2",
+                params![get_language_id_from_name(&tx, &language)?, orphan.rowid],
+            )?;
+            OrphanRepair::Reassigned(language)
+        } else {
+            // Table names come from SQLite itself, not from user input
+            tx.execute(&format!("DELETE FROM \"{}\" WHERE rowid = ?1", orphan.table), params![orphan.rowid])?;
+            OrphanRepair::Deleted
+        };
+        repaired.push((orphan, repair));
     }
+    tx.commit()?;
+    Ok(repaired)
 }
@@ -0,0 +1,253 @@
+use chrono::prelude::*;
+use chrono::{Duration, Months};
+use std::fmt;
+
+/// The `[start, end)` span of time a single date expression describes.
+type Span = (DateTime<Local>, DateTime<Local>);
+
+/// A half-open time range `[start, end)`. A missing bound is unbounded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateRange {
+    pub start: Option<DateTime<Local>>,
+    pub end: Option<DateTime<Local>>,
+}
+
+impl DateRange {
+    /// The unbounded range, matching everything.
+    pub fn all() -> Self {
+        DateRange { start: None, end: None }
+    }
+
+    /// Parses a single expression describing a whole range, as used by `--period`:
+    /// named ranges (`today`, `this-quarter`, `last-month`, ...), the rolling periods
+    /// `week`, `month` and `year`, or any `--since` expression (open-ended up to now).
+    pub fn parse_period(expr: &str, now: DateTime<Local>) -> Result<Self, String> {
+        let (start, end) = parse_span(expr, now)?;
+        // An instant like `3d` means "from then until now"
+        let end = if start == end { now } else { end };
+        Ok(DateRange {
+            start: Some(start),
+            end: Some(end),
+        })
+    }
+
+    /// Builds a range from optional `--since` / `--until` expressions. `since` uses the
+    /// beginning of what it describes and `until` the end, so `--until 2026-01-31`
+    /// includes the whole of January 31st.
+    pub fn from_bounds(since: Option<&str>, until: Option<&str>, now: DateTime<Local>) -> Result<Self, String> {
+        let start = since.map(|expr| parse_span(expr, now).map(|span| span.0)).transpose()?;
+        let end = until.map(|expr| parse_span(expr, now).map(|span| span.1)).transpose()?;
+        if let (Some(start), Some(end)) = (start, end) {
+            if start >= end {
+                return Err(format!(
+                    "--since ({}) must be before --until ({})",
+                    start.format("%Y-%m-%d %H:%M"),
+                    end.format("%Y-%m-%d %H:%M")
+                ));
+            }
+        }
+        Ok(DateRange { start, end })
+    }
+
+    /// Bounds formatted the way SQLite's `CURRENT_TIMESTAMP` stores dates (UTC), so
+    /// they can be compared directly against `DATETIME` columns.
+    pub fn sql_bounds(&self) -> (Option<String>, Option<String>) {
+        let format = |date: DateTime<Local>| date.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S").to_string();
+        (self.start.map(format), self.end.map(format))
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = |date: &DateTime<Local>| {
+            if date.time() == NaiveTime::MIN {
+                date.format("%Y-%m-%d").to_string()
+            } else {
+                date.format("%Y-%m-%d %H:%M").to_string()
+            }
+        };
+        match (&self.start, &self.end) {
+            (Some(start), Some(end)) => write!(f, "{} → {}", format(start), format(end)),
+            (Some(start), None) => write!(f, "since {}", format(start)),
+            (None, Some(end)) => write!(f, "until {}", format(end)),
+            (None, None) => write!(f, "all time"),
+        }
+    }
+}
+
+/// Resolves an expression to the span of time it describes. Days, weeks, months and
+/// quarters span their whole length; relative offsets like `3d` are a single instant.
+fn parse_span(expr: &str, now: DateTime<Local>) -> Result<Span, String> {
+    let normalized = expr.trim().to_lowercase().replace(['-', '_'], " ");
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+    let today = now.date_naive();
+
+    let span = match words.as_slice() {
+        ["today"] => day_span(today),
+        ["yesterday"] => day_span(today - Duration::days(1)),
+        ["week"] => Some((now - Duration::days(7), now)),
+        ["month"] => now.checked_sub_months(Months::new(1)).map(|start| (start, now)),
+        ["year"] => now.checked_sub_months(Months::new(12)).map(|start| (start, now)),
+        ["this", unit] => calendar_span(unit, today, 0)?,
+        ["last", unit] => match unit.parse::<Weekday>() {
+            Ok(weekday) => day_span(previous_weekday(today, weekday)),
+            Err(_) => calendar_span(unit, today, 1)?,
+        },
+        [amount, unit, "ago"] => Some(offset_from(now, amount, unit)?),
+        // Compact offsets such as `3d` or `12h`
+        [compact] if compact.starts_with(|c: char| c.is_ascii_digit()) && compact.ends_with(char::is_alphabetic) => {
+            let split = compact.find(|c: char| !c.is_ascii_digit()).unwrap_or(compact.len());
+            Some(offset_from(now, &compact[..split], &compact[split..])?)
+        }
+        _ => absolute_span(expr.trim()),
+    };
+
+    span.ok_or_else(|| {
+        format!(
+            "Invalid date '{}'. Use a date (2026-01-01), an offset (3d, 2 weeks ago, last monday) \
+             or a named range (today, yesterday, this-week, last-month, this-quarter, ...)",
+            expr
+        )
+    })
+}
+
+fn local_midnight(date: NaiveDate) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()
+}
+
+fn day_span(date: NaiveDate) -> Option<Span> {
+    Some((local_midnight(date)?, local_midnight(date.succ_opt()?)?))
+}
+
+fn previous_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_back = (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday() - 1) % 7 + 1;
+    today - Duration::days(days_back as i64)
+}
+
+/// The calendar week/month/quarter/year containing `today`, shifted `back` units into the past.
+fn calendar_span(
+    unit: &str,
+    today: NaiveDate,
+    back: u32,
+) -> Result<Option<Span>, String> {
+    let (start, end) = match unit {
+        "week" => {
+            let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64 + 7 * back as i64);
+            (monday, monday + Duration::days(7))
+        }
+        "month" | "quarter" | "year" => {
+            let months = match unit {
+                "month" => 1,
+                "quarter" => 3,
+                _ => 12,
+            };
+            let first_month = (today.month0() / months) * months + 1;
+            let current = NaiveDate::from_ymd_opt(today.year(), first_month, 1).unwrap();
+            let start = current - Months::new(months * back);
+            (start, start + Months::new(months))
+        }
+        _ => return Err(format!("Unknown range unit '{}'. Use week, month, quarter, year or a weekday", unit)),
+    };
+    Ok(local_midnight(start).zip(local_midnight(end)))
+}
+
+fn offset_from(now: DateTime<Local>, amount: &str, unit: &str) -> Result<Span, String> {
+    let amount: u32 = amount
+        .parse()
+        .map_err(|_| format!("Invalid amount '{}' in date offset", amount))?;
+    let instant = match unit.trim_end_matches('s') {
+        "h" | "hour" => Some(now - Duration::hours(amount as i64)),
+        "d" | "day" => Some(now - Duration::days(amount as i64)),
+        "w" | "week" => Some(now - Duration::weeks(amount as i64)),
+        "m" | "mo" | "month" => now.checked_sub_months(Months::new(amount)),
+        "y" | "year" => now.checked_sub_months(Months::new(amount * 12)),
+        _ => return Err(format!("Unknown unit '{}' in date offset. Use h, d, w, m or y", unit)),
+    };
+    instant
+        .map(|instant| (instant, instant))
+        .ok_or_else(|| format!("Date offset {}{} is out of range", amount, unit))
+}
+
+fn absolute_span(expr: &str) -> Option<Span> {
+    if let Ok(date) = NaiveDate::parse_from_str(expr, "%Y-%m-%d") {
+        return day_span(date);
+    }
+    if let Ok(month) = NaiveDate::parse_from_str(&format!("{}-01", expr), "%Y-%m-%d") {
+        return local_midnight(month).zip(local_midnight(month + Months::new(1)));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(expr, format) {
+            let instant = Local.from_local_datetime(&datetime).earliest()?;
+            return Some((instant, instant));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wednesday
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 5, 13, 15, 30, 0).unwrap()
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> Option<DateTime<Local>> {
+        local_midnight(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+    }
+
+    #[test]
+    fn test_absolute_dates() {
+        let range = DateRange::from_bounds(Some("2026-01-01"), Some("2026-01-31"), now()).unwrap();
+        assert_eq!(range.start, day(2026, 1, 1));
+        assert_eq!(range.end, day(2026, 2, 1));
+
+        let range = DateRange::from_bounds(Some("2026-02"), None, now()).unwrap();
+        assert_eq!(range.start, day(2026, 2, 1));
+        assert_eq!(range.end, None);
+    }
+
+    #[test]
+    fn test_relative_expressions() {
+        let range = DateRange::from_bounds(Some("3d"), None, now()).unwrap();
+        assert_eq!(range.start, Some(now() - Duration::days(3)));
+
+        let range = DateRange::from_bounds(Some("2 weeks ago"), None, now()).unwrap();
+        assert_eq!(range.start, Some(now() - Duration::days(14)));
+
+        let range = DateRange::from_bounds(Some("last monday"), Some("yesterday"), now()).unwrap();
+        assert_eq!(range.start, day(2026, 5, 11));
+        assert_eq!(range.end, day(2026, 5, 13));
+
+        // "last wednesday" on a Wednesday is a week ago, not today
+        let range = DateRange::from_bounds(Some("last wednesday"), None, now()).unwrap();
+        assert_eq!(range.start, day(2026, 5, 6));
+    }
+
+    #[test]
+    fn test_named_ranges() {
+        let range = DateRange::parse_period("today", now()).unwrap();
+        assert_eq!((range.start, range.end), (day(2026, 5, 13), day(2026, 5, 14)));
+
+        let range = DateRange::parse_period("this-quarter", now()).unwrap();
+        assert_eq!((range.start, range.end), (day(2026, 4, 1), day(2026, 7, 1)));
+
+        let range = DateRange::parse_period("last month", now()).unwrap();
+        assert_eq!((range.start, range.end), (day(2026, 4, 1), day(2026, 5, 1)));
+
+        let range = DateRange::parse_period("this-week", now()).unwrap();
+        assert_eq!((range.start, range.end), (day(2026, 5, 11), day(2026, 5, 18)));
+
+        let range = DateRange::parse_period("week", now()).unwrap();
+        assert_eq!((range.start, range.end), (Some(now() - Duration::days(7)), Some(now())));
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(DateRange::parse_period("fortnight", now()).is_err());
+        assert!(DateRange::parse_period("this-decade", now()).is_err());
+        assert!(DateRange::from_bounds(Some("3x"), None, now()).is_err());
+        assert!(DateRange::from_bounds(Some("today"), Some("yesterday"), now()).is_err());
+    }
+}
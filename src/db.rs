@@ -1,5 +1,5 @@
-use rusqlite::{Connection, ErrorCode, Result};
-use std::fs;
+use crate::migrations;
+use rusqlite::{Connection, Result};
 use std::path::Path;
 
 pub struct Database {
@@ -7,9 +7,11 @@ pub struct Database {
 }
 
 impl Database {
-    /// Creates a new database or opens an existing one
+    /// Creates a new database or opens an existing one, with foreign key
+    /// constraints enforced (SQLite leaves them off by default).
     pub fn new(db_path: &str) -> Result<Self> {
         let conn = Connection::open(Path::new(db_path))?;
+        conn.pragma_update(None, "foreign_keys", true)?;
         Ok(Database { conn })
     }
 
@@ -18,84 +20,9 @@ impl Database {
     }
 
 
-    /// Initialize database by creating tables if they dont exist.
+    /// Initialize database by applying any pending schema migrations.
     pub fn initialize(&self) -> Result<(), rusqlite::Error> {
-        // Check if journal_entries table exists
-        if !self.check_existence("table", "journal_entries")? {
-            // Read the entire content of sql/init.sql file and execute it as a batch
-            let init_sql_path = "sql/init.sql";
-            let init_sql_content = fs::read_to_string(init_sql_path)
-                .map_err(|e| rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error {
-                        code: ErrorCode::Unknown,
-                        extended_code: 1,
-                    },
-                    Some(format!("Failed to read {}: {}", init_sql_path, e)),
-                ))?;
-
-            self.conn.execute_batch(&init_sql_content)
-                .map_err(|e| rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error {
-                        code: ErrorCode::Unknown,
-                        extended_code: 1,
-                    },
-                    Some(format!("Failed to execute batch from {}: {}", init_sql_path, e)),
-                ))?;
-        }
-
-        // Verify all required tables and triggers exist
-        let required_tables = vec![
-            "journal_entries",
-            "languages",
-            "code_snippets",
-            "learning_notes",
-            "snippets_used",
-        ];
-        let required_triggers = vec![
-            "update_timestamp_after_update_code_snippets",
-            "update_timestamp_after_update_languages",
-            "update_timestamp_after_update_learning_notes",
-        ];
-
-        for table in &required_tables {
-            if !self.check_existence("table", table)? {
-                return Err(rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error {
-                        code: ErrorCode::Unknown,
-                        extended_code: 1,
-                    },
-                    Some(format!(
-                        "Table {} doesn't exist. Please run 'sqlite3 your_database_file.db < sql/init.sql'",
-                        table
-                    )),
-                ));
-            }
-        }
-
-        for trigger in &required_triggers {
-            if !self.check_existence("trigger", trigger)? {
-                return Err(rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error {
-                        code: ErrorCode::Unknown,
-                        extended_code: 1,
-                    },
-                    Some(format!(
-                        "Trigger {} doesn't exist. Please run 'sqlite3 your_database_file.db < sql/init.sql'",
-                        trigger
-                    )),
-                ));
-            }
-        }
-
+        migrations::migrate(&self.conn)?;
         Ok(())
     }
-
-    fn check_existence(&self, kind: &str, name: &str) -> Result<bool, rusqlite::Error> {
-        let count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM sqlite_master WHERE type = ?1 AND name = ?2",
-            &[kind, name],
-            |row| row.get(0),
-        )?;
-        Ok(count > 0)
-    }
 }
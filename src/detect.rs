@@ -0,0 +1,300 @@
+use crate::models::Language;
+use std::fmt;
+use std::path::Path;
+
+/// Detections at or above this confidence are used without asking.
+pub const CONFIDENCE_THRESHOLD: f64 = 0.6;
+
+/// What a language guess was based on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionSource {
+    Shebang,
+    Extension,
+    Content,
+}
+
+impl fmt::Display for DetectionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DetectionSource::Shebang => write!(f, "shebang"),
+            DetectionSource::Extension => write!(f, "file extension"),
+            DetectionSource::Content => write!(f, "code contents"),
+        }
+    }
+}
+
+/// The most likely language of a snippet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// Name of the language as stored in the `languages` table.
+    pub language: String,
+    /// How sure the guess is, from 0.0 to 1.0.
+    pub confidence: f64,
+    pub source: DetectionSource,
+}
+
+impl Detection {
+    pub fn is_confident(&self) -> bool {
+        self.confidence >= CONFIDENCE_THRESHOLD
+    }
+}
+
+/// Tokens typical of each seeded language, with how strongly they point to it.
+const TOKEN_WEIGHTS: &[(&str, &[(&str, u32)])] = &[
+    (
+        "rust",
+        &[
+            ("fn ", 2), ("let mut ", 3), ("println!", 3), ("impl ", 2), ("pub fn ", 3), ("use std", 3),
+            ("&str", 2), ("Vec<", 2), ("#[derive", 3), ("::new(", 1), ("-> ", 1), ("unwrap()", 2),
+        ],
+    ),
+    (
+        "js",
+        &[
+            ("console.log", 3), ("function ", 2), ("const ", 1), ("let ", 1), ("=> ", 1), ("require(", 3),
+            ("document.", 3), ("===", 2), ("module.exports", 3), ("undefined", 2),
+        ],
+    ),
+    (
+        "ts",
+        &[
+            ("interface ", 2), (": string", 3), (": number", 3), (": boolean", 3), ("as const", 2),
+            ("export type ", 3), ("readonly ", 2), ("implements ", 1),
+        ],
+    ),
+    (
+        "python",
+        &[
+            ("def ", 3), ("print(", 2), ("self.", 2), ("elif ", 3), ("None", 2), ("__init__", 3),
+            ("import ", 1), ("from ", 1), ("lambda ", 2), ("True", 1), ("False", 1), ("):\n", 2),
+        ],
+    ),
+    (
+        "c++",
+        &[
+            ("#include", 3), ("std::", 3), ("cout", 3), ("int main(", 2), ("nullptr", 3), ("template<", 2),
+            ("namespace ", 2),
+        ],
+    ),
+    (
+        "java",
+        &[
+            ("public class ", 3), ("System.out.println", 4), ("public static void main", 4), ("import java", 4),
+            ("@Override", 3), ("private ", 1), ("String[]", 2),
+        ],
+    ),
+    (
+        "go",
+        &[
+            ("package ", 2), ("func ", 3), (":= ", 3), ("fmt.", 3), ("import (", 2), ("go func", 3),
+            ("chan ", 2), ("err != nil", 4),
+        ],
+    ),
+    (
+        "html",
+        &[
+            ("<html", 4), ("<!doctype", 4), ("<div", 3), ("<body", 3), ("</", 2), ("<p>", 2), ("<a href", 3),
+        ],
+    ),
+    (
+        "css",
+        &[
+            ("color:", 2), ("margin:", 2), ("padding:", 2), ("px;", 2), ("display:", 2), ("@media", 3),
+            ("font-", 1),
+        ],
+    ),
+    (
+        "bash",
+        &[
+            ("echo ", 2), ("$(", 2), ("${", 2), ("; then", 3), ("; do", 3), ("fi\n", 2), ("sudo ", 3),
+            (" | ", 1), (" && ", 1), (" > /dev/null", 3),
+        ],
+    ),
+];
+
+/// Commands that usually start a shell one-liner.
+const SHELL_COMMANDS: &[&str] = &[
+    "apt", "awk", "brew", "cat", "cd", "chmod", "chown", "cp", "curl", "docker", "find", "git", "grep", "kubectl",
+    "ls", "mkdir", "mv", "rm", "sed", "ssh", "tar", "wget", "xargs",
+];
+
+/// Well-known file extensions of the seeded languages.
+fn language_for_extension(extension: &str) -> Option<&'static str> {
+    match extension {
+        "rs" => Some("rust"),
+        "js" | "mjs" | "cjs" | "jsx" => Some("js"),
+        "ts" | "tsx" | "mts" => Some("ts"),
+        "py" | "pyw" => Some("python"),
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "h" => Some("c++"),
+        "java" => Some("java"),
+        "go" => Some("go"),
+        "html" | "htm" => Some("html"),
+        "css" => Some("css"),
+        // Most .sh files are bash scripts, and bash also runs plain sh
+        "sh" | "bash" => Some("bash"),
+        _ => None,
+    }
+}
+
+/// Guesses the language of `code`, optionally read from `file_name`, among `languages`.
+/// A shebang wins over the file extension, which wins over scoring the code's tokens.
+pub fn detect_language(code: &str, file_name: Option<&str>, languages: &[Language]) -> Option<Detection> {
+    let detected = |name: &str, confidence: f64, source: DetectionSource| {
+        resolve(name, languages).map(|language| Detection {
+            language: language.name.clone(),
+            confidence,
+            source,
+        })
+    };
+
+    if let Some(interpreter) = shebang_interpreter(code) {
+        if let Some(detection) = detected(&interpreter, 1.0, DetectionSource::Shebang) {
+            return Some(detection);
+        }
+    }
+
+    let extension = file_name
+        .and_then(|name| Path::new(name).extension())
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    if let Some(extension) = extension {
+        let name = language_for_extension(&extension).unwrap_or(&extension);
+        if let Some(detection) = detected(name, 0.95, DetectionSource::Extension) {
+            return Some(detection);
+        }
+    }
+
+    let mut scores: Vec<(&str, u32)> = TOKEN_WEIGHTS
+        .iter()
+        .filter(|(name, _)| resolve(name, languages).is_some())
+        .map(|(name, tokens)| (*name, token_score(code, name, tokens)))
+        .filter(|(_, score)| *score > 0)
+        .collect();
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    let (best, best_score) = *scores.first()?;
+    let runner_up = scores.get(1).map_or(0, |(_, score)| *score);
+
+    // Sure when one language clearly dominates and the evidence is more than a single weak token
+    let dominance = best_score as f64 / (best_score + runner_up) as f64;
+    let evidence = (best_score as f64 / 4.0).min(1.0);
+    detected(best, dominance * evidence, DetectionSource::Content)
+}
+
+/// The language named by a name or alias.
+fn resolve<'a>(name: &str, languages: &'a [Language]) -> Option<&'a Language> {
+    languages
+        .iter()
+        .find(|language| language.name == name || language.aliases.iter().any(|alias| alias == name))
+}
+
+/// The interpreter of a `#!` line, without version numbers: `#!/usr/bin/env python3` gives `python`.
+fn shebang_interpreter(code: &str) -> Option<String> {
+    let line = code.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(match program {
+        "node" | "nodejs" => "js".to_string(),
+        "deno" | "ts-node" => "ts".to_string(),
+        other => other.to_string(),
+    })
+}
+
+fn token_score(code: &str, language: &str, tokens: &[(&str, u32)]) -> u32 {
+    let mut score: u32 = tokens
+        .iter()
+        .filter(|(token, _)| code.contains(token))
+        .map(|(_, weight)| weight)
+        .sum();
+    if language == "bash" {
+        let starts_with_command = code.lines().any(|line| {
+            let command = line.trim_start().trim_start_matches("$ ").split_whitespace().next();
+            command.is_some_and(|command| SHELL_COMMANDS.contains(&command))
+        });
+        if starts_with_command {
+            score += 3;
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    fn languages() -> Vec<Language> {
+        [
+            ("rust", vec!["rs"]),
+            ("js", vec!["javascript"]),
+            ("ts", vec![]),
+            ("python", vec!["py"]),
+            ("go", vec![]),
+            ("bash", vec!["sh"]),
+            ("kotlin", vec!["kt"]),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(id, (name, aliases))| Language {
+            id: id as i64 + 1,
+            name: name.to_string(),
+            aliases: aliases.into_iter().map(String::from).collect(),
+            created_at: Local::now(),
+            updated_at: Local::now(),
+        })
+        .collect()
+    }
+
+    fn detect(code: &str, file_name: Option<&str>) -> Option<Detection> {
+        detect_language(code, file_name, &languages())
+    }
+
+    #[test]
+    fn test_shebang_wins() {
+        let detection = detect("#!/usr/bin/env python3\nconsole.log(1)", Some("script.js")).unwrap();
+        assert_eq!(detection.language, "python");
+        assert_eq!(detection.source, DetectionSource::Shebang);
+
+        assert_eq!(detect("#!/bin/sh\nls", None).unwrap().language, "bash");
+        assert_eq!(detect("#!/usr/bin/env node\n", None).unwrap().language, "js");
+    }
+
+    #[test]
+    fn test_file_extension() {
+        let detection = detect("x = 1", Some("/tmp/Notes.PY")).unwrap();
+        assert_eq!(detection.language, "python");
+        assert_eq!(detection.source, DetectionSource::Extension);
+        assert!(detection.is_confident());
+
+        // Aliases double as extensions for languages without a built-in mapping
+        assert_eq!(detect("val x = 1", Some("main.kt")).unwrap().language, "kotlin");
+    }
+
+    #[test]
+    fn test_token_scoring() {
+        let detection = detect("fn main() { println!(\"hi\"); }", None).unwrap();
+        assert_eq!(detection.language, "rust");
+        assert!(detection.is_confident());
+
+        let detection = detect("if err != nil {\n\treturn err\n}", None).unwrap();
+        assert_eq!(detection.language, "go");
+
+        let detection = detect("find . -name '*.tmp' | xargs rm", None).unwrap();
+        assert_eq!(detection.language, "bash");
+        assert!(detection.is_confident());
+    }
+
+    #[test]
+    fn test_weak_or_missing_evidence() {
+        let detection = detect("print(x)", None).unwrap();
+        assert_eq!(detection.language, "python");
+        assert!(!detection.is_confident());
+
+        assert!(detect("hello world", None).is_none());
+        // Only languages in the table are considered
+        assert!(detect_language("#include <vector>\nstd::cout", None, &languages()).is_none());
+    }
+}
@@ -0,0 +1,173 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Text and tags written by the user in a journal entry template.
+#[derive(Debug, PartialEq)]
+pub struct ComposedEntry {
+    pub entry: String,
+    pub tags: Option<String>,
+}
+
+/// The editor command from `$VISUAL`, then `$EDITOR`, falling back to `vi`.
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Opens `initial` in the user's editor on a temporary file ending in `extension`
+/// and returns the saved contents. The editor value may include arguments, e.g. `code --wait`.
+pub fn edit_text(initial: &str, extension: &str) -> io::Result<String> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let path: PathBuf = env::temp_dir().join(format!("clinotes-{}-{}.{}", std::process::id(), nanos, extension));
+    fs::write(&path, initial)?;
+
+    let command = editor_command();
+    let mut parts = command.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program).args(parts).arg(&path).status();
+
+    let result = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!("Editor '{}' exited with {}", command, status))),
+        Err(e) => Err(io::Error::new(
+            e.kind(),
+            format!("Failed to launch editor '{}': {}", command, e),
+        )),
+    };
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// File extension for code in `language`, so editors pick the right syntax mode.
+pub fn extension_for_language(language: &str) -> &'static str {
+    match language {
+        "rust" => "rs",
+        "js" => "js",
+        "ts" => "ts",
+        "python" => "py",
+        "c++" => "cpp",
+        "java" => "java",
+        "go" => "go",
+        "html" => "html",
+        "css" => "css",
+        "bash" | "sh" => "sh",
+        "zsh" => "zsh",
+        "fish" => "fish",
+        _ => "txt",
+    }
+}
+
+/// Reads a whole entry from standard input, e.g. `git log | clinotes journal add -`.
+pub fn read_stdin() -> io::Result<String> {
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text)?;
+    Ok(text.trim_end().to_string())
+}
+
+/// Separates the entry text from the instructions and `tags:` line in a journal template.
+/// The entry above it is kept as written, so markdown headings and `tags:` text survive.
+pub const TEMPLATE_SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Builds the editor template for a journal entry. Existing `tags` are written as an
+/// active `tags:` line below the scissors line, otherwise a commented-out one hints at the syntax.
+pub fn journal_template(entry: &str, tags: Option<&str>, popular_tags: &[String]) -> String {
+    let mut template = String::new();
+    template.push_str(entry);
+    template.push_str("\n\n");
+    template.push_str(TEMPLATE_SCISSORS);
+    template.push('\n');
+    match tags {
+        Some(tags) if !tags.trim().is_empty() => template.push_str(&format!("tags: {}\n", tags.trim())),
+        _ => template.push_str("# tags: rust, learning\n"),
+    }
+    template.push_str("#\n");
+    template.push_str("# Write your journal entry above the line; an empty entry aborts.\n");
+    template.push_str("# Everything below the line is ignored, except a 'tags:' line: uncomment\n");
+    template.push_str("# and edit it with comma-separated tags to tag the entry.\n");
+    if !popular_tags.is_empty() {
+        template.push_str(&format!("#\n# Your most used tags: {}\n", popular_tags.join(", ")));
+    }
+    template
+}
+
+/// Extracts the entry text and tags from an edited journal template. Without the
+/// scissors line, the whole text is the entry.
+pub fn parse_journal_template(text: &str) -> ComposedEntry {
+    let mut lines = text.lines();
+    let entry: Vec<&str> = lines.by_ref().take_while(|line| line.trim_end() != TEMPLATE_SCISSORS).collect();
+
+    let mut tags = None;
+    for line in lines.filter(|line| !line.starts_with('#')) {
+        if let Some(value) = line.trim().strip_prefix("tags:") {
+            tags = Some(value.trim().to_string()).filter(|t| !t.is_empty());
+        }
+    }
+
+    ComposedEntry {
+        entry: entry.join("\n").trim().to_string(),
+        tags,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_untouched_template_is_empty() {
+        let template = journal_template("", None, &["rust".to_string()]);
+        assert_eq!(
+            parse_journal_template(&template),
+            ComposedEntry {
+                entry: String::new(),
+                tags: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_entry_and_uncommented_tags() {
+        let template = journal_template("", None, &[]);
+        let edited = format!("Paired on the parser.\n\nIt went well.{}", template)
+            .replace("# tags: rust, learning", "tags: parsing, pairing");
+        let composed = parse_journal_template(&edited);
+        assert_eq!(composed.entry, "Paired on the parser.\n\nIt went well.");
+        assert_eq!(composed.tags.as_deref(), Some("parsing, pairing"));
+    }
+
+    #[test]
+    fn test_existing_tags_round_trip() {
+        let template = journal_template("Old text", Some("rust, cli"), &[]);
+        let composed = parse_journal_template(&template);
+        assert_eq!(composed.entry, "Old text");
+        assert_eq!(composed.tags.as_deref(), Some("rust, cli"));
+    }
+
+    #[test]
+    fn test_markdown_entry_round_trip() {
+        let entry = "# Heading\n\nSome text.\ntags: x is not a tag line here\n\n## Subheading";
+        let template = journal_template(entry, Some("rust"), &["rust".to_string()]);
+        let composed = parse_journal_template(&template);
+        assert_eq!(composed.entry, entry);
+        assert_eq!(composed.tags.as_deref(), Some("rust"));
+
+        // Removing the scissors line keeps everything as the entry
+        assert_eq!(
+            parse_journal_template("# Heading\ntags: x\n"),
+            ComposedEntry {
+                entry: "# Heading\ntags: x".to_string(),
+                tags: None
+            }
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use std::io;
+use std::process::{Command, ExitStatus};
+use std::time::{Duration, Instant};
+
+/// The shell that runs snippets in `language`, or `None` for languages that are not shells.
+pub fn shell_for_language(language: &str) -> Option<&'static str> {
+    match language {
+        "bash" => Some("bash"),
+        "sh" => Some("sh"),
+        "zsh" => Some("zsh"),
+        "fish" => Some("fish"),
+        _ => None,
+    }
+}
+
+/// Runs `script` with `shell -c`, attached to the current terminal, and reports how it
+/// exited and how long it took.
+pub fn run_script(shell: &str, script: &str) -> io::Result<(ExitStatus, Duration)> {
+    let started = Instant::now();
+    let status = Command::new(shell)
+        .arg("-c")
+        .arg(script)
+        .status()
+        .map_err(|e| io::Error::new(e.kind(), format!("Failed to start {}: {}", shell, e)))?;
+    Ok((status, started.elapsed()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_shells_run() {
+        assert_eq!(shell_for_language("bash"), Some("bash"));
+        assert_eq!(shell_for_language("zsh"), Some("zsh"));
+        assert_eq!(shell_for_language("python"), None);
+    }
+
+    #[test]
+    fn test_run_script_reports_exit_code() {
+        let (status, _) = run_script("sh", "exit 3").unwrap();
+        assert_eq!(status.code(), Some(3));
+        assert!(run_script("no-such-shell-here", "true").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::editor::extension_for_language;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Names of the bundled color themes.
+pub fn theme_names() -> Vec<String> {
+    ThemeSet::load_defaults().themes.into_keys().collect()
+}
+
+/// Renders `code` for a terminal: colored for `language` with `theme`, and numbered.
+/// Languages without a bundled grammar are numbered but left uncolored.
+pub fn highlight(code: &str, language: &str, theme: &str) -> Result<String, String> {
+    let themes = ThemeSet::load_defaults();
+    let theme = themes.themes.get(theme).ok_or_else(|| {
+        format!("Unknown theme '{}'. Available themes: {}", theme, theme_names().join(", "))
+    })?;
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    let mut highlighter = HighlightLines::new(syntax_for_language(&syntaxes, language), theme);
+
+    let width = code.lines().count().max(1).to_string().len();
+    let mut rendered = String::new();
+    for (index, line) in LinesWithEndings::from(code).enumerate() {
+        let ranges = highlighter
+            .highlight_line(line, &syntaxes)
+            .map_err(|e| format!("Failed to highlight line {}: {}", index + 1, e))?;
+        rendered.push_str(&format!("\x1b[2m{:>width$} │\x1b[0m ", index + 1, width = width));
+        rendered.push_str(&as_24_bit_terminal_escaped(&ranges, false));
+        rendered.push_str("\x1b[0m");
+    }
+    if !rendered.ends_with('\n') {
+        rendered.push('\n');
+    }
+    Ok(rendered)
+}
+
+fn syntax_for_language<'a>(syntaxes: &'a SyntaxSet, language: &str) -> &'a SyntaxReference {
+    let extension = match extension_for_language(language) {
+        // No TypeScript grammar is bundled; JavaScript covers most of it
+        "ts" => "js",
+        "txt" => language,
+        extension => extension,
+    };
+    syntaxes
+        .find_syntax_by_extension(extension)
+        .or_else(|| syntaxes.find_syntax_by_token(language))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_numbers_and_colors_lines() {
+        let rendered = highlight("fn main() {\n    let x = 1;\n}", "rust", DEFAULT_THEME).unwrap();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("1 │"));
+        assert!(lines[2].contains("3 │"));
+        assert!(rendered.contains("\x1b[38;2;"));
+        assert!(rendered.contains("main"));
+    }
+
+    #[test]
+    fn test_unknown_language_is_plain() {
+        let syntaxes = SyntaxSet::load_defaults_newlines();
+        assert_eq!(syntax_for_language(&syntaxes, "kotlin").name, "Plain Text");
+        assert_eq!(syntax_for_language(&syntaxes, "bash").name, "Bourne Again Shell (bash)");
+        assert_eq!(syntax_for_language(&syntaxes, "ts").name, "JavaScript");
+    }
+
+    #[test]
+    fn test_unknown_theme() {
+        let err = highlight("x", "rust", "no-such-theme").unwrap_err();
+        assert!(err.contains(DEFAULT_THEME));
+    }
+}
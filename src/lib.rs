@@ -1,3 +1,16 @@
+pub mod ai;
+pub mod clipboard;
+pub mod config;
+pub mod date_range;
+pub mod detect;
 pub mod db;
+pub mod editor;
+pub mod exec;
+pub mod highlight;
+pub mod markdown;
+pub mod migrations;
 pub mod models;
-pub mod dao;
\ No newline at end of file
+pub mod scan;
+pub mod template;
+pub mod watch;
+pub mod dao;
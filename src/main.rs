@@ -1,9 +1,33 @@
 use cli_notes::db;
+use cli_notes::migrations;
 use cli_notes::dao;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
 use std::path::PathBuf;
-use cli_notes::dao::{create_journal_entry, get_journal_entries_by_period, search_journal_entries, summarize_journal_entries, create_code_snippet, read_code_snippet, create_learning_note};
-use cli_notes::models::{CodeSnippet, JournalEntry};
-use clap::{Parser, Subcommand};
+use std::time::Duration;
+use cli_notes::ai::JournalAnalyzer;
+use cli_notes::clipboard::Clipboard;
+use cli_notes::config::Config;
+use cli_notes::date_range::DateRange;
+use cli_notes::editor;
+use cli_notes::exec;
+use cli_notes::highlight;
+use cli_notes::scan;
+use cli_notes::template;
+use cli_notes::watch::{self, NoteWatcher};
+use cli_notes::dao::{
+    add_language_alias, create_language, delete_language, list_languages, remove_language_alias,
+    count_journal_entries, create_code_snippet, delete_code_snippet, list_code_snippets, search_code_snippets,
+    update_code_snippet, create_journal_entry_with_analyzer, create_learning_note,
+    delete_journal_entry, get_journal_entries_in_range, list_journal_entries, list_tags, merge_tags,
+    read_code_snippet, read_journal_entry, rename_tag, search_journal_entries_ranked, summarize_journal_entries,
+    update_journal_entry_with_analyzer,
+};
+use cli_notes::models::{
+    CodeSnippet, JournalEntry, LearningNote, LinkTarget, NoteImport, NoteLink, NoteSync, OrphanRepair, OrphanRow,
+    SnippetUsed, TagSource,
+};
+use clap::{ArgGroup, Parser, Subcommand};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -28,34 +52,113 @@ enum Commands {
         #[command(subcommand)]
         command: SnippetCommands,
     },
+    /// Snippet language operations
+    Lang {
+        #[command(subcommand)]
+        command: LangCommands,
+    },
     /// Learning note operations
     Note {
         #[command(subcommand)]
         command: NoteCommands,
     },
+    /// Journal tag operations
+    Tag {
+        #[command(subcommand)]
+        command: TagCommands,
+    },
+    /// Database maintenance operations
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+    /// Keep learning notes up to date as files change in the directories added with 'note scan'
+    Watch {
+        /// How long files must be left alone before a burst of changes is applied
+        #[arg(long, value_name = "MS", default_value_t = watch::DEFAULT_DEBOUNCE.as_millis() as u64)]
+        debounce_ms: u64,
+    },
 }
 
 #[derive(Subcommand, Debug)]
 enum JournalCommands {
     /// Add a new journal entry with AI analysis
     Add {
-        /// The journal entry content
-        entry: String,
+        /// The journal entry content. Omit it to write the entry in $VISUAL/$EDITOR, or pass '-' to read stdin
+        entry: Option<String>,
         /// Optional tags for the entry
         #[arg(long)]
         tags: Option<String>,
+        /// Link a code snippet used in this entry. Can be repeated
+        #[arg(long = "snippet", value_name = "ID")]
+        snippets: Vec<i64>,
     },
     /// Generate AI summary for a specific time period
     Summarize {
-        /// Time period (week, month, year)
-        #[arg(long, default_value = "week")]
+        /// Time period: week, month, year, or a named range like today, this-quarter, last-month
+        #[arg(long, default_value = "week", conflicts_with_all = ["since", "until"])]
         period: String,
+        /// Start of the range: a date (2026-01-01), an offset (3d, 2 weeks ago, last monday) or a named range
+        #[arg(long)]
+        since: Option<String>,
+        /// End of the range (inclusive), in the same formats as --since
+        #[arg(long)]
+        until: Option<String>,
     },
     /// Ask AI questions about your journal entries
     Insights {
         /// Your question about the journal entries
         query: String,
     },
+    /// List journal entries, newest first
+    List {
+        /// Page number to show, starting at 1
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+        /// Number of entries per page
+        #[arg(long, default_value_t = 10)]
+        per_page: usize,
+        /// Only entries on or after this date (see `journal summarize --help`)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only entries on or before this date
+        #[arg(long)]
+        until: Option<String>,
+        /// Only entries carrying this exact tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Show a journal entry in full
+    Show {
+        /// The ID of the entry to show
+        id: i64,
+    },
+    /// Change the text or tags of a journal entry and re-run the AI analysis
+    Edit {
+        /// The ID of the entry to edit
+        id: i64,
+        /// The new entry content. Omit it (and --tags) to edit the entry in $VISUAL/$EDITOR
+        entry: Option<String>,
+        /// The new comma-separated tags
+        #[arg(long)]
+        tags: Option<String>,
+    },
+    /// Delete a journal entry
+    Delete {
+        /// The ID of the entry to delete
+        id: i64,
+        /// Do not ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Full-text search over journal entries, best matches first
+    Search {
+        /// FTS query, e.g. '"exact phrase"', 'rust NOT async' or 'borr*'
+        query: String,
+        /// Maximum number of results to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -63,23 +166,160 @@ enum SnippetCommands {
     /// Add a new code snippet
     Add {
         /// The code content of the snippet
+        #[arg(long, required_unless_present_any = ["file", "from_clipboard"], conflicts_with_all = ["file", "from_clipboard"])]
+        code: Option<String>,
+        /// Read the code from a file instead
+        #[arg(long, conflicts_with = "from_clipboard")]
+        file: Option<PathBuf>,
+        /// Take the code from the clipboard instead
+        #[arg(long)]
+        from_clipboard: bool,
+        /// The programming language of the snippet, by name or alias (e.g., rust, py).
+        /// Detected from the code when omitted
         #[arg(long)]
-        code: String,
-        /// The programming language of the snippet (e.g., rust, python)
+        lang: Option<String>,
+        /// A short title, e.g. "Email validation regex"
         #[arg(long)]
-        lang: String,
+        title: Option<String>,
+        /// What the snippet does or when to use it
+        #[arg(long)]
+        desc: Option<String>,
+        /// Where the snippet came from, e.g. a Stack Overflow URL
+        #[arg(long)]
+        source: Option<String>,
+        /// Comma-separated tags
+        #[arg(long)]
+        tags: Option<String>,
     },
     /// Show a specific code snippet by its ID
     Show {
         /// The ID of the snippet to show
         id: i64,
+        /// Color theme for syntax highlighting on a terminal
+        #[arg(long, default_value = highlight::DEFAULT_THEME)]
+        theme: String,
+    },
+    /// List the most recent code snippets
+    List {
+        /// Only snippets in this language
+        #[arg(long)]
+        lang: Option<String>,
+        /// Maximum number of snippets to list
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Find snippets whose code, title, description or source contain the given text, or tagged with it
+    Search {
+        /// The text to look for (case-insensitive)
+        text: String,
+    },
+    /// Fill in a snippet's {{placeholders}} and print the result, asking for missing values
+    Render {
+        /// The ID of the snippet to render
+        id: i64,
+        /// A placeholder value, e.g. --set port=8080. Can be repeated
+        #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_assignment)]
+        values: Vec<(String, String)>,
+    },
+    /// Fill in a shell snippet's {{placeholders}}, asking for missing values, and run it
+    Run {
+        /// The ID of the snippet to run
+        id: i64,
+        /// A placeholder value, e.g. --set port=8080. Can be repeated
+        #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_assignment)]
+        values: Vec<(String, String)>,
+        /// Run without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Run a shell snippet after showing the command and asking for confirmation
+    Exec {
+        /// The ID of the snippet to run
+        id: i64,
+        /// A placeholder value, e.g. --set port=8080. Can be repeated
+        #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_assignment)]
+        values: Vec<(String, String)>,
+        /// Run without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Record that a snippet was used in a journal entry or learning note
+    #[command(group(ArgGroup::new("target").required(true).args(["journal", "note"])))]
+    Link {
+        /// The ID of the snippet
+        id: i64,
+        /// The journal entry it was used in
+        #[arg(long)]
+        journal: Option<i64>,
+        /// The learning note it was used in
+        #[arg(long)]
+        note: Option<i64>,
+        /// How the snippet was used
+        #[arg(long)]
+        desc: Option<String>,
+    },
+    /// Remove a link between a snippet and a journal entry or learning note
+    #[command(group(ArgGroup::new("target").required(true).args(["journal", "note"])))]
+    Unlink {
+        /// The ID of the snippet
+        id: i64,
+        /// The journal entry to unlink
+        #[arg(long)]
+        journal: Option<i64>,
+        /// The learning note to unlink
+        #[arg(long)]
+        note: Option<i64>,
+    },
+    /// Copy a snippet's code to the clipboard
+    Copy {
+        /// The ID of the snippet to copy
+        id: i64,
+    },
+    /// Edit a snippet's code in $VISUAL/$EDITOR
+    Edit {
+        /// The ID of the snippet to edit
+        id: i64,
+    },
+    /// Delete a code snippet
+    Rm {
+        /// The ID of the snippet to delete
+        id: i64,
+        /// Do not ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum LangCommands {
+    /// Add a new language
+    Add {
+        /// The language name, e.g. kotlin
+        name: String,
+    },
+    /// List languages with their aliases and how many snippets use them
+    List,
+    /// Remove a language that no snippet uses
+    Rm {
+        /// The language name
+        name: String,
+    },
+    /// Add an alias for a language, or remove one with --remove
+    Alias {
+        /// The alias, e.g. py
+        alias: String,
+        /// The language the alias stands for
+        #[arg(required_unless_present = "remove")]
+        language: Option<String>,
+        /// Remove the alias instead
+        #[arg(long, conflicts_with = "language")]
+        remove: bool,
+    },
+}
 
 #[derive(Subcommand, Debug)]
 enum NoteCommands {
-    /// Add a new learning note by its file path
+    /// Add a markdown file as a learning note and index its content
     Add {
         /// The path to the note file
         path: String,
@@ -89,33 +329,440 @@ enum NoteCommands {
         /// The ID of the note to show
         id: i64,
     },
+    /// Add every markdown file under a directory, skipping files that are already notes
+    Scan {
+        /// The directory to scan. Hidden files and files ignored by .gitignore are skipped
+        dir: PathBuf,
+        /// Files to add, relative to the directory
+        #[arg(long, default_value = scan::DEFAULT_GLOB)]
+        glob: String,
+        /// Skip paths or file names matching this glob, e.g. 'node_modules'. Can be repeated
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+    },
+    /// List learning notes
+    List {
+        /// Only notes whose file changed since it was last indexed
+        #[arg(long, conflicts_with = "missing")]
+        stale: bool,
+        /// Only notes whose file was missing at the last sync
+        #[arg(long)]
+        missing: bool,
+    },
+    /// Re-index changed notes, relink moved ones and flag missing ones
+    Sync,
+    /// Print the links between learning notes as a graph, e.g. for Graphviz: 'note graph | dot -Tsvg'
+    Graph {
+        /// Output format
+        #[arg(long, default_value = "dot", value_parser = ["dot"])]
+        format: String,
+    },
+    /// Full-text search over the content of learning notes, best matches first
+    Search {
+        /// FTS query, e.g. '"exact phrase"', 'rust NOT async' or 'borr*'
+        query: String,
+        /// Maximum number of results to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
 }
 
+#[derive(Subcommand, Debug)]
+enum TagCommands {
+    /// List all tags with how many entries use them
+    List,
+    /// Rename a tag on every entry that uses it
+    Rename {
+        /// The current tag name
+        old: String,
+        /// The new tag name
+        new: String,
+    },
+    /// Merge one or more tags into another
+    Merge {
+        /// The tags to merge away
+        #[arg(required = true)]
+        sources: Vec<String>,
+        /// The tag to merge them into
+        #[arg(long)]
+        into: String,
+    },
+}
 
+#[derive(Subcommand, Debug)]
+enum DbCommands {
+    /// Apply all pending schema migrations
+    Migrate,
+    /// Show the schema version and pending migrations
+    Status,
+    /// Report rows pointing at missing parents, e.g. links to deleted snippets
+    Check {
+        /// Delete orphaned links and move snippets with a missing language to a valid one
+        #[arg(long)]
+        repair: bool,
+    },
+}
 
 
 
-fn main() {
-    use std::env;
 
-    if let Ok(path) = env::current_dir() {
-        println!("The current directory is {}", path.display());
+
+/// Whether output may use colors: stdout is a terminal and `NO_COLOR` is not set.
+fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Markers used to highlight search matches: bold on a terminal, plain text otherwise.
+fn highlight_markers() -> (&'static str, &'static str) {
+    if use_color() {
+        ("\x1b[1m", "\x1b[0m")
+    } else {
+        ("**", "**")
+    }
+}
+
+fn build_analyzer(config: &Config) -> Box<dyn JournalAnalyzer> {
+    config.ai.build_analyzer().unwrap_or_else(|e| {
+        eprintln!("❌ Error setting up AI backend: {}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Lets the user write a journal entry in their editor, starting from `entry` and `tags`.
+fn compose_journal_entry(conn: &rusqlite::Connection, entry: &str, tags: Option<&str>) -> editor::ComposedEntry {
+    let popular_tags: Vec<String> = dao::count_tags(conn, Some(TagSource::User))
+        .unwrap_or_default()
+        .into_iter()
+        .take(8)
+        .map(|(name, _)| name)
+        .collect();
+    let template = editor::journal_template(entry, tags, &popular_tags);
+    match editor::edit_text(&template, "md") {
+        Ok(text) => editor::parse_journal_template(&text),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The name of a language, or its ID when it cannot be looked up.
+fn language_name(conn: &rusqlite::Connection, language_id: i64) -> String {
+    dao::get_language_from_id(conn, language_id).unwrap_or_else(|_| format!("#{}", language_id))
+}
+
+fn print_snippet_list(conn: &rusqlite::Connection, snippets: &[CodeSnippet]) {
+    for snippet in snippets {
+        println!("#{:<5} [{}] {:<8} {}",
+            snippet.id,
+            snippet.created_at.format("%Y-%m-%d"),
+            language_name(conn, snippet.language_id),
+            match &snippet.title {
+                Some(title) => first_line(title, 60),
+                None => first_line(&snippet.full_code, 60),
+            }
+        );
+    }
+}
+
+/// The link target picked with `--journal` or `--note`; clap makes sure exactly one is given.
+fn link_target(journal: Option<i64>, note: Option<i64>) -> LinkTarget {
+    match (journal, note) {
+        (Some(id), _) => LinkTarget::Journal(id),
+        (None, Some(id)) => LinkTarget::Note(id),
+        (None, None) => unreachable!("clap requires --journal or --note"),
+    }
+}
+
+fn describe_target(target: LinkTarget) -> String {
+    match target {
+        LinkTarget::Journal(id) => format!("journal entry #{}", id),
+        LinkTarget::Note(id) => format!("learning note #{}", id),
     }
+}
+
+/// One "used in" line for `snippet show`: the journal entry or note, with a preview.
+fn describe_link(conn: &rusqlite::Connection, link: &SnippetUsed) -> String {
+    let (target, preview) = match link.target() {
+        Some(target @ LinkTarget::Journal(id)) => (
+            target,
+            read_journal_entry(conn, id).ok().flatten().map(|entry| {
+                format!("[{}] {}", entry.date.format("%Y-%m-%d"), first_line(&entry.entry, 50))
+            }),
+        ),
+        Some(target @ LinkTarget::Note(id)) => (
+            target,
+            dao::read_learning_note(conn, id).ok().flatten().map(|note| note.file_name),
+        ),
+        None => return format!("link #{} (no target)", link.id),
+    };
+    let mut line = describe_target(target);
+    if let Some(preview) = preview {
+        line.push_str(&format!(" {}", preview));
+    }
+    if let Some(description) = &link.description {
+        line.push_str(&format!(" — {}", description));
+    }
+    line
+}
+
+/// One line per snippet linked to a journal entry or note.
+fn describe_linked_snippet(conn: &rusqlite::Connection, link: &SnippetUsed) -> String {
+    let title = match read_code_snippet(conn, link.snippet_id) {
+        Ok(Some(snippet)) => snippet.title.unwrap_or_else(|| first_line(&snippet.full_code, 50)),
+        _ => "(missing)".to_string(),
+    };
+    match &link.description {
+        Some(description) => format!("#{} {} — {}", link.snippet_id, title, description),
+        None => format!("#{} {}", link.snippet_id, title),
+    }
+}
+
+/// One line about what `note sync` or `watch` did to a note, `None` if nothing changed.
+fn describe_note_sync(note: &LearningNote, sync: &NoteSync) -> Option<String> {
+    match sync {
+        NoteSync::Unchanged => None,
+        NoteSync::Added => Some(format!("+ {} (#{})", note.file_path, note.id)),
+        NoteSync::Updated => Some(format!("~ {} (#{})", note.file_path, note.id)),
+        NoteSync::Restored => Some(format!("✓ {} (#{}) is back", note.file_path, note.id)),
+        NoteSync::Moved(from) => Some(format!("→ {} moved to {} (#{})", from, note.file_path, note.id)),
+        NoteSync::Missing => Some(format!("✗ {} (#{}) is missing", note.file_path, note.id)),
+        NoteSync::Unreadable(error) => Some(format!("⚠️  {}", error)),
+        NoteSync::Unresolved => Some(format!(
+            "⚠️  {} (#{}) was added with a relative path; sync from the directory it was added in",
+            note.file_path, note.id
+        )),
+    }
+}
+
+/// The notes and their links in Graphviz DOT. Broken links point at dashed nodes.
+fn note_graph_dot(notes: &[LearningNote], links: &[NoteLink]) -> String {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut dot = String::from("digraph notes {\n");
+    for note in notes {
+        let label = note.title.as_deref().unwrap_or(&note.file_name);
+        dot.push_str(&format!("  n{} [label={}];\n", note.id, quote(label)));
+    }
+    for link in links {
+        match link.target_note_id {
+            Some(target) => dot.push_str(&format!("  n{} -> n{};\n", link.source_note_id, target)),
+            None => {
+                let node = quote(&format!("broken:{}", link.written()));
+                dot.push_str(&format!("  {} [label={}, style=dashed];\n", node, quote(&link.written())));
+                dot.push_str(&format!("  n{} -> {} [style=dashed];\n", link.source_note_id, node));
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn describe_orphan(orphan: &OrphanRow) -> String {
+    format!("{} row {} references a missing {} row", orphan.table, orphan.rowid, orphan.parent)
+}
+
+/// Detects the language of a new snippet. Confident guesses are used as is; otherwise
+/// the user is asked to confirm the best guess, and the command fails without one.
+fn detect_language_or_exit(conn: &rusqlite::Connection, code: &str, file_name: Option<&str>) -> String {
+    match dao::detect_snippet_language(conn, code, file_name) {
+        Ok(Some(detection)) if detection.is_confident() => {
+            println!(
+                "🔍 Detected {} from the {} ({:.0}% confident)",
+                detection.language,
+                detection.source,
+                detection.confidence * 100.0
+            );
+            detection.language
+        }
+        Ok(Some(detection)) => {
+            let prompt = format!(
+                "🤔 This looks like {}, but only {:.0}% confident. Use it?",
+                detection.language,
+                detection.confidence * 100.0
+            );
+            if std::io::stdin().is_terminal() && confirm(&prompt) {
+                detection.language
+            } else {
+                eprintln!("❌ Could not tell the snippet's language for sure. Pass it with --lang.");
+                std::process::exit(1);
+            }
+        }
+        Ok(None) => {
+            eprintln!("❌ Could not detect the snippet's language. Pass it with --lang.");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("❌ Error detecting the snippet's language: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
 
-    // Replace the old db_path line with this block
-    let db_path: PathBuf = match dirs::config_dir() {
+/// Parses a `name=value` assignment from the command line.
+fn parse_assignment(text: &str) -> Result<(String, String), String> {
+    match text.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got '{}'", text)),
+    }
+}
+
+/// Values for every parameter of `snippet`: the given ones, then answers typed on the
+/// terminal (an empty answer keeps the default). Off a terminal, defaults are used as is.
+fn snippet_values(snippet: &CodeSnippet, given: Vec<(String, String)>) -> HashMap<String, String> {
+    let mut values: HashMap<String, String> = HashMap::new();
+    for (name, value) in given {
+        if !snippet.parameters.iter().any(|parameter| parameter.name == name) {
+            eprintln!("❌ Snippet #{} has no parameter '{}'", snippet.id, name);
+            std::process::exit(2);
+        }
+        values.insert(name, value);
+    }
+    if !std::io::stdin().is_terminal() {
+        return values;
+    }
+    for parameter in &snippet.parameters {
+        if values.contains_key(&parameter.name) {
+            continue;
+        }
+        match &parameter.default {
+            Some(default) => eprint!("{} [{}]: ", parameter.name, default),
+            None => eprint!("{}: ", parameter.name),
+        }
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).is_err() {
+            break;
+        }
+        let answer = answer.trim_end_matches(['\r', '\n']);
+        if !answer.is_empty() {
+            values.insert(parameter.name.clone(), answer.to_string());
+        }
+    }
+    values
+}
+
+/// Asks a yes/no question on the terminal. Anything but "y" or "yes" means no.
+fn confirm(prompt: &str) -> bool {
+    print!("{} [y/N] ", prompt);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// The first non-blank line of `text`, cut to at most `max_chars` characters.
+fn first_line(text: &str, max_chars: usize) -> String {
+    let line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+    if line.chars().count() > max_chars {
+        format!("{}…", line.chars().take(max_chars - 1).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Resolves `--period` / `--since` / `--until` into a date range, exiting on invalid input.
+/// Explicit bounds replace the default period; clap rejects an explicit `--period` with them.
+fn date_range_from_args(period: Option<&str>, since: Option<&str>, until: Option<&str>) -> DateRange {
+    let now = chrono::Local::now();
+    let range = match (period, since, until) {
+        (_, None, None) => match period {
+            Some(period) => DateRange::parse_period(period, now),
+            None => Ok(DateRange::all()),
+        },
+        _ => DateRange::from_bounds(since, until, now),
+    };
+    range.unwrap_or_else(|e| {
+        eprintln!("❌ {}", e);
+        std::process::exit(2);
+    })
+}
+
+/// Renders a shell snippet, asking for missing values, shows the command and runs it
+/// once confirmed, recording the run. Exits with the command's status.
+fn exec_snippet(conn: &rusqlite::Connection, id: i64, values: Vec<(String, String)>, yes: bool) {
+    let snippet = match read_code_snippet(conn, id) {
+        Ok(Some(snippet)) => snippet,
+        Ok(None) => {
+            eprintln!("🔍 Snippet with ID {} not found.", id);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("❌ Error reading snippet: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let lang = language_name(conn, snippet.language_id);
+    let Some(shell) = exec::shell_for_language(&lang) else {
+        eprintln!("❌ Snippet #{} is written in {}; only shell snippets can be executed.", id, lang);
+        std::process::exit(2);
+    };
+    let values = snippet_values(&snippet, values);
+    let command = match template::render(&snippet.full_code, &values) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("❌ {}. Pass them with --set NAME=VALUE.", e);
+            std::process::exit(2);
+        }
+    };
+
+    println!("--- {} ---\n{}\n---", shell, command.trim_end());
+    if !yes && !confirm("▶️  Run this command?") {
+        println!("Aborted.");
+        std::process::exit(1);
+    }
+    let (status, duration) = match exec::run_script(shell, &command) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = dao::record_snippet_run(conn, id, &command, status.code(), duration) {
+        eprintln!("❌ Error recording the run: {}", e);
+    }
+    match status.code() {
+        Some(0) => eprintln!("✅ Finished in {:.2?}", duration),
+        Some(code) => {
+            eprintln!("❌ Exited with status {} after {:.2?}", code, duration);
+            std::process::exit(code);
+        }
+        None => {
+            eprintln!("❌ Killed by a signal after {:.2?}", duration);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let opts: CliNotes = CliNotes::parse();
+
+    // Everything lives in a clinotes directory under the user's config dir
+    let app_dir: PathBuf = match dirs::config_dir() {
         Some(mut path) => {
             path.push("clinotes"); // Create a directory for our app
             if !path.exists() {
                 std::fs::create_dir_all(&path).expect("Failed to create config directory");
             }
-            path.push("clinotes.db"); // The final DB file path
             path
         }
         None => {
             // Fallback for rare cases where config dir can't be found
             eprintln!("Warning: Could not find a config directory. Using current directory.");
-            PathBuf::from("clidblocal.db")
+            PathBuf::new()
+        }
+    };
+    let db_path = if app_dir.as_os_str().is_empty() {
+        PathBuf::from("clidblocal.db")
+    } else {
+        app_dir.join("clinotes.db") // The final DB file path
+    };
+
+    let config = match Config::load(&app_dir.join("config.json")) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ Error loading config: {}", e);
+            std::process::exit(1);
         }
     };
 
@@ -128,34 +775,67 @@ fn main() {
         }
     };
 
-    //Initialize the database ( create tables if they don't exist)
-    if let Err(e) = database.initialize() {
-        eprintln!("❌ Error initializing database: {}", e);
-        std::process::exit(1);
+    //Initialize the database (apply pending migrations). The db commands manage migrations themselves.
+    if !matches!(opts.command, Some(Commands::Db { .. })) {
+        if let Err(e) = database.initialize() {
+            eprintln!("❌ Error initializing database: {}", e);
+            std::process::exit(1);
+        }
     }
 
-
-    let opts: CliNotes = CliNotes::parse();
     match opts.command {
         Some(Commands::Journal { command }) => {
             match command {
-                JournalCommands::Add { entry, tags } => {
+                JournalCommands::Add { entry, tags, snippets } => {
+                    for snippet_id in &snippets {
+                        if !matches!(read_code_snippet(database.conn(), *snippet_id), Ok(Some(_))) {
+                            eprintln!("🔍 Snippet with ID {} not found.", snippet_id);
+                            std::process::exit(1);
+                        }
+                    }
+                    let (entry, tags) = match entry.as_deref() {
+                        Some("-") => match editor::read_stdin() {
+                            Ok(text) => (text, tags),
+                            Err(e) => {
+                                eprintln!("❌ Error reading stdin: {}", e);
+                                std::process::exit(1);
+                            }
+                        },
+                        Some(_) => (entry.unwrap(), tags),
+                        None => {
+                            let composed = compose_journal_entry(database.conn(), "", tags.as_deref());
+                            (composed.entry, composed.tags)
+                        }
+                    };
+                    if entry.trim().is_empty() {
+                        eprintln!("❌ Aborting: the journal entry is empty.");
+                        std::process::exit(1);
+                    }
+
+                    let analyzer = build_analyzer(&config);
                     let journal_entry = JournalEntry::new(entry, tags);
-                    match create_journal_entry(database.conn(), &journal_entry) {
+                    match create_journal_entry_with_analyzer(database.conn(), &journal_entry, analyzer.as_ref()) {
                         Ok(id) => {
                             println!("✅ Journal entry created successfully with ID: {}", id);
                             println!("🤖 AI analysis completed - sentiment and tags automatically generated!");
+                            for snippet_id in snippets {
+                                match dao::link_snippet(database.conn(), snippet_id, LinkTarget::Journal(id), None) {
+                                    Ok(_) => println!("🔗 Linked snippet #{}", snippet_id),
+                                    Err(e) => eprintln!("❌ Error linking snippet #{}: {}", snippet_id, e),
+                                }
+                            }
                         }
                         Err(e) => println!("❌ Error creating journal entry: {}", e),
                     }
                 }
-                JournalCommands::Summarize { period } => {
-                    match get_journal_entries_by_period(database.conn(), &period) {
+                JournalCommands::Summarize { period, since, until } => {
+                    let range = date_range_from_args(Some(&period), since.as_deref(), until.as_deref());
+                    match get_journal_entries_in_range(database.conn(), &range) {
                         Ok(entries) => {
                             if entries.is_empty() {
-                                println!("📝 No journal entries found for the {} period.", period);
+                                println!("📝 No journal entries found for {}.", range);
                             } else {
-                                println!("📊 AI Summary for the past {}:", period);
+                                println!("📊 AI Summary for {}:", range);
                                 println!("Found {} entries", entries.len());
                                 
                                 let summary = summarize_journal_entries(&entries);
@@ -165,96 +845,808 @@ fn main() {
                                 println!("  Negative: {} entries", summary.negative_count);
                                 println!("  Neutral: {} entries", summary.neutral_count);
                                 
-                                println!("\n🏷️  Most common topics: {}", summary.common_topics);
+                                println!("\n🏷️  Most common topics:");
+                                if summary.common_topics.is_empty() {
+                                    println!("  (no topics yet)");
+                                }
+                                for (topic, count) in summary.common_topics.iter().take(5) {
+                                    let percentage = *count as f64 * 100.0 / summary.total_entries as f64;
+                                    println!("  {:<20} {:>3} entries ({:.0}%)", topic, count, percentage);
+                                }
                             }
                         }
                         Err(e) => println!("❌ Error retrieving entries: {}", e),
                     }
                 }
                 JournalCommands::Insights { query } => {
-                    match search_journal_entries(database.conn(), &query) {
-                        Ok(entries) => {
-                            if entries.is_empty() {
+                    match search_journal_entries_ranked(database.conn(), &query, None, highlight_markers()) {
+                        Ok(results) => {
+                            if results.is_empty() {
                                 println!("🔍 No entries found matching your query: '{}'", query);
                             } else {
                                 println!("🧠 AI Insights for query: '{}'", query);
-                                println!("Found {} relevant entries:\n", entries.len());
+                                println!("Found {} relevant entries:\n", results.len());
                                 
-                                for (i, entry) in entries.iter().take(3).enumerate() {
+                                for (i, result) in results.iter().take(3).enumerate() {
                                     println!("{}. [{}] {}", 
                                         i + 1, 
-                                        entry.date.format("%Y-%m-%d"),
-                                        entry.entry.chars().take(100).collect::<String>()
+                                        result.entry.date.format("%Y-%m-%d"),
+                                        result.snippet
                                     );
-                                    if let Some(sentiment) = &entry.sentiment {
+                                    if let Some(sentiment) = &result.entry.sentiment {
                                         println!("   Sentiment: {}", sentiment);
                                     }
-                                    if let Some(ai_tags) = &entry.ai_tags {
+                                    if let Some(ai_tags) = &result.entry.ai_tags {
                                         println!("   AI Tags: {}", ai_tags);
                                     }
                                     println!();
                                 }
                                 
-                                if entries.len() > 3 {
-                                    println!("... and {} more entries", entries.len() - 3);
+                                if results.len() > 3 {
+                                    println!("... and {} more entries", results.len() - 3);
                                 }
                             }
                         }
                         Err(e) => println!("❌ Error searching entries: {}", e),
                     }
                 }
+                JournalCommands::List { page, per_page, since, until, tag } => {
+                    let range = date_range_from_args(None, since.as_deref(), until.as_deref());
+                    let page = page.max(1);
+                    let per_page = per_page.max(1);
+                    let listing = count_journal_entries(database.conn(), &range, tag.as_deref()).and_then(|total| {
+                        let entries = list_journal_entries(
+                            database.conn(),
+                            &range,
+                            tag.as_deref(),
+                            per_page,
+                            (page - 1) * per_page,
+                        )?;
+                        Ok((total, entries))
+                    });
+                    match listing {
+                        Ok((0, _)) => println!("📝 No journal entries found."),
+                        Ok((total, entries)) => {
+                            for entry in &entries {
+                                println!("#{:<5} [{}] {:<8} {}",
+                                    entry.id,
+                                    entry.date.format("%Y-%m-%d %H:%M"),
+                                    entry.sentiment.as_deref().unwrap_or("-"),
+                                    first_line(&entry.entry, 60)
+                                );
+                            }
+                            let pages = total.div_ceil(per_page);
+                            println!("\nPage {} of {} ({} entries)", page, pages, total);
+                        }
+                        Err(e) => eprintln!("❌ Error listing entries: {}", e),
+                    }
+                }
+                JournalCommands::Show { id } => {
+                    match read_journal_entry(database.conn(), id) {
+                        Ok(Some(entry)) => {
+                            println!("--- Journal Entry ID: {} ---", entry.id);
+                            println!("Date: {}", entry.date.format("%Y-%m-%d %H:%M"));
+                            println!("Sentiment: {}", entry.sentiment.as_deref().unwrap_or("-"));
+                            let tag_names = |source: TagSource| {
+                                entry.tag_list.iter()
+                                    .filter(|tag| tag.source == source)
+                                    .map(|tag| tag.name.as_str())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            };
+                            println!("Tags: {}", tag_names(TagSource::User));
+                            println!("AI Tags: {}", tag_names(TagSource::Ai));
+                            let links = dao::list_linked_snippets(database.conn(), LinkTarget::Journal(id)).unwrap_or_default();
+                            if !links.is_empty() {
+                                println!("Snippets:");
+                                for link in links {
+                                    println!("  {}", describe_linked_snippet(database.conn(), &link));
+                                }
+                            }
+                            println!("---\n{}\n---", entry.entry);
+                        }
+                        Ok(None) => println!("🔍 Journal entry with ID {} not found.", id),
+                        Err(e) => eprintln!("❌ Error reading journal entry: {}", e),
+                    }
+                }
+                JournalCommands::Edit { id, entry, tags } => {
+                    let mut journal_entry = match read_journal_entry(database.conn(), id) {
+                        Ok(Some(journal_entry)) => journal_entry,
+                        Ok(None) => {
+                            eprintln!("🔍 Journal entry with ID {} not found.", id);
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Error reading journal entry: {}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                    if entry.is_none() && tags.is_none() {
+                        let composed = compose_journal_entry(
+                            database.conn(),
+                            &journal_entry.entry,
+                            journal_entry.tags.as_deref(),
+                        );
+                        if composed.entry.is_empty() {
+                            eprintln!("❌ Aborting: the journal entry is empty.");
+                            std::process::exit(1);
+                        }
+                        journal_entry.entry = composed.entry;
+                        journal_entry.tags = composed.tags;
+                    }
+                    if let Some(entry) = entry {
+                        journal_entry.entry = entry;
+                    }
+                    if tags.is_some() {
+                        journal_entry.tags = tags;
+                    }
+
+                    let analyzer = build_analyzer(&config);
+                    match update_journal_entry_with_analyzer(database.conn(), &journal_entry, analyzer.as_ref()) {
+                        Ok(()) => {
+                            println!("✅ Journal entry {} updated successfully", id);
+                            println!("🤖 AI analysis re-run on the new content!");
+                        }
+                        Err(e) => eprintln!("❌ Error updating journal entry: {}", e),
+                    }
+                }
+                JournalCommands::Delete { id, yes } => {
+                    if !yes && !confirm(&format!("Delete journal entry {}?", id)) {
+                        println!("Aborted.");
+                        return;
+                    }
+                    match delete_journal_entry(database.conn(), id) {
+                        Ok(()) => println!("🗑️  Journal entry {} deleted", id),
+                        Err(e) => eprintln!("❌ Error deleting journal entry: {}", e),
+                    }
+                }
+                JournalCommands::Search { query, limit } => {
+                    match search_journal_entries_ranked(database.conn(), &query, Some(limit), highlight_markers()) {
+                        Ok(results) => {
+                            if results.is_empty() {
+                                println!("🔍 No entries found matching your query: '{}'", query);
+                            }
+                            for result in results {
+                                println!("#{} [{}] {}", 
+                                    result.entry.id,
+                                    result.entry.date.format("%Y-%m-%d"),
+                                    result.snippet
+                                );
+                            }
+                        }
+                        Err(e) => eprintln!("❌ Error searching entries: {}", e),
+                    }
+                }
             }
         }
         Some(Commands::Snippet { command }) => {
             match command {
-                SnippetCommands::Add { code, lang } => {
+                SnippetCommands::Add { code, file, from_clipboard, lang, title, desc, source, tags } => {
+                    let code = match (code, &file) {
+                        (Some(code), _) => code,
+                        (None, Some(file)) => std::fs::read_to_string(file).unwrap_or_else(|e| {
+                            eprintln!("❌ Error reading {}: {}", file.display(), e);
+                            std::process::exit(1);
+                        }),
+                        (None, None) if from_clipboard => Clipboard::from_env().paste().unwrap_or_else(|e| {
+                            eprintln!("❌ Error reading the clipboard: {}", e);
+                            std::process::exit(1);
+                        }),
+                        (None, None) => unreachable!("clap requires --code, --file or --from-clipboard"),
+                    };
+                    if code.trim().is_empty() {
+                        eprintln!("❌ Aborting: the snippet is empty.");
+                        std::process::exit(1);
+                    }
+                    let file_name = file.as_ref().and_then(|file| file.file_name()).and_then(|name| name.to_str());
+                    let lang = lang.unwrap_or_else(|| detect_language_or_exit(database.conn(), &code, file_name));
                     let new_snippet = CodeSnippet {
-                        id: 0, // ID is set by the database
-                        full_code: code,
-                        created_at: chrono::Local::now(),
-                        updated_at: chrono::Local::now(),
-                        language_id: 0, // This will be looked up by the DAO
+                        title,
+                        description: desc,
+                        source_url: source,
+                        tags,
+                        ..CodeSnippet::new(code)
                     };
-                    match create_code_snippet(database.conn(), &new_snippet, &lang) {
+                    match create_code_snippet(database.conn(), &new_snippet, Some(&lang), file_name) {
                         Ok(id) => println!("✅ Snippet created successfully with ID: {}", id),
                         Err(e) => eprintln!("❌ Error creating snippet: {}", e),
                     }
                 }
-                SnippetCommands::Show { id } => {
+                SnippetCommands::Show { id, theme } => {
+                    if !highlight::theme_names().contains(&theme) {
+                        eprintln!(
+                            "❌ Unknown theme '{}'. Available themes: {}",
+                            theme,
+                            highlight::theme_names().join(", ")
+                        );
+                        std::process::exit(2);
+                    }
                     match read_code_snippet(database.conn(), id) {
                         Ok(Some(snippet)) => {
+                            let lang = language_name(database.conn(), snippet.language_id);
                             println!("--- Snippet ID: {} ---", snippet.id);
-                            println!("Language ID: {}", snippet.language_id); // In a future task, we can look up the name
+                            if let Some(title) = &snippet.title {
+                                println!("Title: {}", title);
+                            }
+                            println!("Language: {}", lang);
+                            if let Some(tags) = &snippet.tags {
+                                println!("Tags: {}", tags);
+                            }
+                            if let Some(source) = &snippet.source_url {
+                                println!("Source: {}", source);
+                            }
+                            if !snippet.parameters.is_empty() {
+                                let parameters: Vec<String> = snippet
+                                    .parameters
+                                    .iter()
+                                    .map(|parameter| match &parameter.default {
+                                        Some(default) => format!("{} (default: {})", parameter.name, default),
+                                        None => parameter.name.clone(),
+                                    })
+                                    .collect();
+                                println!("Parameters: {}", parameters.join(", "));
+                            }
                             println!("Created At: {}", snippet.created_at);
-                            println!("---\n{} \n---", snippet.full_code);
+                            let last_run = dao::list_snippet_runs(database.conn(), id, 1).unwrap_or_default();
+                            if let Some(last) = last_run.first() {
+                                let runs = dao::count_snippet_runs(database.conn(), id).unwrap_or(1);
+                                let exit = last.exit_code.map_or("killed".to_string(), |code| format!("exit {}", code));
+                                println!("Runs: {} (last {}, {})", runs, last.ran_at.format("%Y-%m-%d %H:%M"), exit);
+                            }
+                            let links = dao::list_snippet_links(database.conn(), id).unwrap_or_default();
+                            if !links.is_empty() {
+                                println!("Used in:");
+                                for link in links {
+                                    println!("  {}", describe_link(database.conn(), &link));
+                                }
+                            }
+                            if let Some(description) = &snippet.description {
+                                println!("\n{}", description);
+                            }
+                            if use_color() {
+                                match highlight::highlight(&snippet.full_code, &lang, &theme) {
+                                    Ok(code) => print!("---\n{}---\n", code),
+                                    Err(e) => {
+                                        eprintln!("❌ {}", e);
+                                        std::process::exit(2);
+                                    }
+                                }
+                            } else {
+                                println!("---\n{} \n---", snippet.full_code);
+                            }
                         }
                         Ok(None) => println!("🔍 Snippet with ID {} not found.", id),
                         Err(e) => eprintln!("❌ Error reading snippet: {}", e),
                     }
                 }
+                SnippetCommands::List { lang, limit } => {
+                    match list_code_snippets(database.conn(), lang.as_deref(), limit) {
+                        Ok(snippets) if snippets.is_empty() => println!("📝 No snippets found."),
+                        Ok(snippets) => print_snippet_list(database.conn(), &snippets),
+                        Err(e) => eprintln!("❌ Error listing snippets: {}", e),
+                    }
+                }
+                SnippetCommands::Search { text } => {
+                    match search_code_snippets(database.conn(), &text) {
+                        Ok(snippets) if snippets.is_empty() => println!("🔍 No snippets found containing '{}'", text),
+                        Ok(snippets) => print_snippet_list(database.conn(), &snippets),
+                        Err(e) => eprintln!("❌ Error searching snippets: {}", e),
+                    }
+                }
+                SnippetCommands::Render { id, values } => {
+                    let snippet = match read_code_snippet(database.conn(), id) {
+                        Ok(Some(snippet)) => snippet,
+                        Ok(None) => {
+                            eprintln!("🔍 Snippet with ID {} not found.", id);
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Error reading snippet: {}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                    let values = snippet_values(&snippet, values);
+                    match template::render(&snippet.full_code, &values) {
+                        Ok(rendered) if rendered.ends_with('\n') => print!("{}", rendered),
+                        Ok(rendered) => println!("{}", rendered),
+                        Err(e) => {
+                            eprintln!("❌ {}. Pass them with --set NAME=VALUE.", e);
+                            std::process::exit(2);
+                        }
+                    }
+                }
+                SnippetCommands::Exec { id, values, yes } | SnippetCommands::Run { id, values, yes } => {
+                    exec_snippet(database.conn(), id, values, yes);
+                }
+                SnippetCommands::Link { id, journal, note, desc } => {
+                    let target = link_target(journal, note);
+                    match dao::link_snippet(database.conn(), id, target, desc.as_deref()) {
+                        Ok(_) => println!("🔗 Linked snippet #{} to {}", id, describe_target(target)),
+                        Err(e) => eprintln!("❌ Error linking snippet: {}", e),
+                    }
+                }
+                SnippetCommands::Unlink { id, journal, note } => {
+                    let target = link_target(journal, note);
+                    match dao::unlink_snippet(database.conn(), id, target) {
+                        Ok(()) => println!("✅ Unlinked snippet #{} from {}", id, describe_target(target)),
+                        Err(e) => eprintln!("❌ Error unlinking snippet: {}", e),
+                    }
+                }
+                SnippetCommands::Copy { id } => {
+                    match read_code_snippet(database.conn(), id) {
+                        Ok(Some(snippet)) => {
+                            let clipboard = Clipboard::from_env();
+                            match clipboard.copy(&snippet.full_code) {
+                                Ok(()) => println!(
+                                    "📋 Copied snippet #{} to the clipboard (via {})",
+                                    id,
+                                    clipboard.copy_method()
+                                ),
+                                Err(e) => {
+                                    eprintln!("❌ Error copying to the clipboard: {}", e);
+                                    std::process::exit(1);
+                                }
+                            }
+                        }
+                        Ok(None) => {
+                            eprintln!("🔍 Snippet with ID {} not found.", id);
+                            std::process::exit(1);
+                        }
+                        Err(e) => eprintln!("❌ Error reading snippet: {}", e),
+                    }
+                }
+                SnippetCommands::Edit { id } => {
+                    let mut snippet = match read_code_snippet(database.conn(), id) {
+                        Ok(Some(snippet)) => snippet,
+                        Ok(None) => {
+                            eprintln!("🔍 Snippet with ID {} not found.", id);
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Error reading snippet: {}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                    let lang = language_name(database.conn(), snippet.language_id);
+                    let code = match editor::edit_text(&snippet.full_code, editor::extension_for_language(&lang)) {
+                        Ok(code) => code,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                    if code.trim().is_empty() {
+                        eprintln!("❌ Aborting: the snippet is empty. Use 'snippet rm' to delete it.");
+                        std::process::exit(1);
+                    }
+                    if code == snippet.full_code {
+                        println!("No changes made to snippet {}.", id);
+                        return;
+                    }
+                    snippet.full_code = code;
+                    match update_code_snippet(database.conn(), &snippet, &lang) {
+                        Ok(()) => println!("✅ Snippet {} updated successfully", id),
+                        Err(e) => eprintln!("❌ Error updating snippet: {}", e),
+                    }
+                }
+                SnippetCommands::Rm { id, yes } => {
+                    if !yes && !confirm(&format!("Delete snippet {}?", id)) {
+                        println!("Aborted.");
+                        return;
+                    }
+                    match delete_code_snippet(database.conn(), id) {
+                        Ok(()) => println!("🗑️  Snippet {} deleted", id),
+                        Err(e) => eprintln!("❌ Error deleting snippet: {}", e),
+                    }
+                }
             }
         }
         Some(Commands::Note { command }) => {
             match command {
                 NoteCommands::Add { path } => {
                     match create_learning_note(database.conn(), &path) {
-                        Ok(id) => println!("✅ Note linked successfully with ID: {}", id),
-                        Err(e) => eprintln!("❌ Error linking note: {}", e),
+                        Ok(id) => {
+                            let title = dao::read_learning_note(database.conn(), id).ok().flatten().and_then(|note| note.title);
+                            match title {
+                                Some(title) => println!("✅ Note '{}' linked successfully with ID: {}", title, id),
+                                None => println!("✅ Note linked successfully with ID: {}", id),
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Error linking note: {}", e);
+                            std::process::exit(1);
+                        }
                     }
                 }
                 NoteCommands::Show { id } => {
                     match dao::read_learning_note(database.conn(), id) {
                         Ok(Some(note)) => {
                             println!("Note ID: {}", note.id);
+                            println!("Title: {}", note.title.as_deref().unwrap_or("-"));
                             println!("File Path: {}", note.file_path);
                             println!("File Name: {}", note.file_name);
+                            println!("Words: {}", note.word_count);
                             println!("Created At: {}", note.created_at);
                             println!("Updated At: {}", note.updated_at);
+                            if !note.headings.is_empty() {
+                                println!("Outline:");
+                                for heading in &note.headings {
+                                    println!("  {}{}", "  ".repeat(heading.level as usize - 1), heading.text);
+                                }
+                            }
+                            let links = dao::list_note_links(database.conn()).unwrap_or_default();
+                            let linked_note = |id: i64| {
+                                dao::read_learning_note(database.conn(), id).ok().flatten().map_or_else(
+                                    || format!("#{}", id),
+                                    |note| format!("#{} {}", note.id, note.title.as_deref().unwrap_or(&note.file_name)),
+                                )
+                            };
+                            let (resolved, broken): (Vec<&NoteLink>, Vec<&NoteLink>) = links
+                                .iter()
+                                .filter(|link| link.source_note_id == id)
+                                .partition(|link| link.target_note_id.is_some());
+                            if !resolved.is_empty() {
+                                println!("Links:");
+                                for link in resolved {
+                                    if let Some(target) = link.target_note_id {
+                                        println!("  → {} ({})", linked_note(target), link.written());
+                                    }
+                                }
+                            }
+                            if !broken.is_empty() {
+                                println!("Broken links:");
+                                for link in broken {
+                                    println!("  ✗ {}", link.written());
+                                }
+                            }
+                            let mut backlinks: Vec<i64> = links
+                                .iter()
+                                .filter(|link| link.target_note_id == Some(id) && link.source_note_id != id)
+                                .map(|link| link.source_note_id)
+                                .collect();
+                            backlinks.dedup();
+                            if !backlinks.is_empty() {
+                                println!("Backlinks:");
+                                for source in backlinks {
+                                    println!("  ← {}", linked_note(source));
+                                }
+                            }
                         }
                         Ok(None) => println!("Note with ID {} not found.", id),
                         Err(e) => eprintln!("Error reading note: {}", e),
                     }
                 }
+                NoteCommands::Scan { dir, glob, exclude } => {
+                    let found = scan::find_files(&dir, &glob, &exclude).unwrap_or_else(|e| {
+                        eprintln!("❌ Error scanning {}: {}", dir.display(), e);
+                        std::process::exit(1);
+                    });
+                    for error in &found.errors {
+                        eprintln!("⚠️  {}", error);
+                    }
+                    if let Err(e) = dao::record_note_root(database.conn(), &dir, &glob, &exclude) {
+                        eprintln!("⚠️  Could not remember {} for 'note sync': {}", dir.display(), e);
+                    }
+
+                    let (mut added, mut changed, mut skipped, mut failed) = (0, 0, 0, 0);
+                    for file in &found.files {
+                        let shown = file.strip_prefix(&dir).unwrap_or(file).display();
+                        match dao::import_learning_note(database.conn(), file) {
+                            Ok(NoteImport::Added(id)) => {
+                                added += 1;
+                                println!("  + {} (#{})", shown, id);
+                            }
+                            Ok(NoteImport::Changed(id)) => {
+                                changed += 1;
+                                println!("  ~ {} (#{})", shown, id);
+                            }
+                            Ok(NoteImport::Unchanged(_)) => skipped += 1,
+                            Ok(NoteImport::Duplicate(id)) => {
+                                skipped += 1;
+                                println!("  = {} (same content as #{})", shown, id);
+                            }
+                            Err(e) => {
+                                failed += 1;
+                                eprintln!("❌ {}: {}", shown, e);
+                            }
+                        }
+                    }
+                    let failed = if failed > 0 { format!(", {} failed", failed) } else { String::new() };
+                    println!(
+                        "✅ Scanned {} file(s): {} added, {} changed, {} skipped{}",
+                        found.files.len(), added, changed, skipped, failed
+                    );
+                }
+                NoteCommands::List { stale, missing } => {
+                    let notes = if stale {
+                        dao::list_stale_learning_notes(database.conn())
+                    } else {
+                        dao::list_learning_notes(database.conn())
+                            .map(|notes| notes.into_iter().filter(|note| !missing || note.missing_since.is_some()).collect())
+                    };
+                    match notes {
+                        Ok(notes) if notes.is_empty() => println!("🔍 No notes found."),
+                        Ok(notes) => {
+                            for note in notes {
+                                let title = note.title.as_deref().unwrap_or(&note.file_name);
+                                match note.missing_since {
+                                    Some(since) => println!(
+                                        "#{} {} ({}) — missing since {}",
+                                        note.id, title, note.file_path, since.format("%Y-%m-%d")
+                                    ),
+                                    None => println!("#{} {} ({})", note.id, title, note.file_path),
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Error listing notes: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                NoteCommands::Sync => {
+                    match dao::sync_learning_notes(database.conn()) {
+                        Ok(synced) => {
+                            let (mut updated, mut moved, mut missing, mut skipped) = (0, 0, 0, 0);
+                            for (note, sync) in &synced {
+                                match sync {
+                                    NoteSync::Unchanged => {}
+                                    NoteSync::Added | NoteSync::Updated | NoteSync::Restored => updated += 1,
+                                    NoteSync::Moved(_) => moved += 1,
+                                    NoteSync::Missing => missing += 1,
+                                    NoteSync::Unreadable(_) | NoteSync::Unresolved => skipped += 1,
+                                }
+                                match describe_note_sync(note, sync) {
+                                    Some(line) if matches!(sync, NoteSync::Unreadable(_) | NoteSync::Unresolved) => {
+                                        eprintln!("{}", line)
+                                    }
+                                    Some(line) => println!("  {}", line),
+                                    None => {}
+                                }
+                            }
+                            let skipped = if skipped > 0 { format!(", {} skipped", skipped) } else { String::new() };
+                            println!(
+                                "✅ Synced {} note(s): {} updated, {} moved, {} missing{}",
+                                synced.len(), updated, moved, missing, skipped
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Error syncing notes: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                NoteCommands::Graph { format: _ } => {
+                    let graph = dao::list_learning_notes(database.conn())
+                        .and_then(|notes| Ok((notes, dao::list_note_links(database.conn())?)));
+                    match graph {
+                        Ok((notes, links)) => print!("{}", note_graph_dot(&notes, &links)),
+                        Err(e) => {
+                            eprintln!("❌ Error reading note links: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                NoteCommands::Search { query, limit } => {
+                    match dao::search_learning_notes(database.conn(), &query, Some(limit), highlight_markers()) {
+                        Ok(results) => {
+                            if results.is_empty() {
+                                println!("🔍 No notes found matching your query: '{}'", query);
+                            }
+                            for result in results {
+                                let title = result.note.title.as_deref().unwrap_or(&result.note.file_name);
+                                println!("#{} {} ({})", result.note.id, title, result.note.file_path);
+                                println!("    {}", result.snippet.replace('\n', " "));
+                            }
+                        }
+                        Err(e) => eprintln!("❌ Error searching notes: {}", e),
+                    }
+                }
+            }
+        }
+        Some(Commands::Lang { command }) => {
+            match command {
+                LangCommands::Add { name } => {
+                    match create_language(database.conn(), &name) {
+                        Ok(_) => println!("✅ Added language '{}'", name.trim().to_lowercase()),
+                        Err(e) => eprintln!("❌ Error adding language: {}", e),
+                    }
+                }
+                LangCommands::List => {
+                    match list_languages(database.conn()) {
+                        Ok(languages) => {
+                            println!("{:<12} {:>8}  ALIASES", "LANGUAGE", "SNIPPETS");
+                            for (language, snippets) in languages {
+                                println!("{:<12} {:>8}  {}", language.name, snippets, language.aliases.join(", "));
+                            }
+                        }
+                        Err(e) => eprintln!("❌ Error listing languages: {}", e),
+                    }
+                }
+                LangCommands::Rm { name } => {
+                    match delete_language(database.conn(), &name) {
+                        Ok(()) => println!("🗑️  Removed language '{}'", name),
+                        Err(e) => eprintln!("❌ Error removing language: {}", e),
+                    }
+                }
+                LangCommands::Alias { alias, language, remove } => {
+                    if remove {
+                        match remove_language_alias(database.conn(), &alias) {
+                            Ok(()) => println!("🗑️  Removed alias '{}'", alias),
+                            Err(e) => eprintln!("❌ Error removing alias: {}", e),
+                        }
+                    } else {
+                        let language = language.unwrap_or_default();
+                        match add_language_alias(database.conn(), &alias, &language) {
+                            Ok(()) => println!("✅ '{}' is now an alias for '{}'", alias, language),
+                            Err(e) => eprintln!("❌ Error adding alias: {}", e),
+                        }
+                    }
+                }
+            }
+        }
+        Some(Commands::Tag { command }) => {
+            match command {
+                TagCommands::List => {
+                    match list_tags(database.conn()) {
+                        Ok(tags) if tags.is_empty() => println!("🏷️  No tags yet."),
+                        Ok(tags) => {
+                            println!("{:<24} {:>6} {:>6}", "TAG", "USER", "AI");
+                            for tag in tags {
+                                println!("{:<24} {:>6} {:>6}", tag.name, tag.user_count, tag.ai_count);
+                            }
+                        }
+                        Err(e) => eprintln!("❌ Error listing tags: {}", e),
+                    }
+                }
+                TagCommands::Rename { old, new } => {
+                    match rename_tag(database.conn(), &old, &new) {
+                        Ok(()) => println!("✅ Renamed tag '{}' to '{}'", old, new),
+                        Err(e) => eprintln!("❌ Error renaming tag: {}", e),
+                    }
+                }
+                TagCommands::Merge { sources, into } => {
+                    let sources: Vec<&str> = sources.iter().map(String::as_str).collect();
+                    match merge_tags(database.conn(), &sources, &into) {
+                        Ok(()) => println!("✅ Merged {} into '{}'", sources.join(", "), into),
+                        Err(e) => eprintln!("❌ Error merging tags: {}", e),
+                    }
+                }
+            }
+        }
+        Some(Commands::Db { command }) => {
+            match command {
+                DbCommands::Migrate => {
+                    match migrations::migrate(database.conn()) {
+                        Ok(applied) if applied.is_empty() => {
+                            println!("✅ Database is up to date (schema version {}).", migrations::latest_version());
+                        }
+                        Ok(applied) => {
+                            for version in applied {
+                                println!("⬆️  Applied migration {}", version);
+                            }
+                            println!("✅ Database migrated to schema version {}.", migrations::latest_version());
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Error migrating database: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                DbCommands::Status => {
+                    let status = migrations::current_version(database.conn())
+                        .and_then(|version| Ok((version, migrations::pending_migrations(database.conn())?)));
+                    match status {
+                        Ok((version, pending)) => {
+                            println!("Database: {}", db_path.display());
+                            println!("Schema version: {} (latest: {})", version, migrations::latest_version());
+                            if pending.is_empty() {
+                                println!("✅ No pending migrations.");
+                            } else {
+                                println!("⏳ Pending migrations:");
+                                for migration in pending {
+                                    println!("  {:04} {}", migration.version, migration.name);
+                                }
+                                println!("💡 Run 'clinotes db migrate' to apply them.");
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Error reading database status: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                DbCommands::Check { repair } => {
+                    if !migrations::pending_migrations(database.conn()).map(|p| p.is_empty()).unwrap_or(false) {
+                        eprintln!("❌ The database schema is not up to date. Run 'clinotes db migrate' first.");
+                        std::process::exit(1);
+                    }
+                    if repair {
+                        match dao::repair_orphans(database.conn()) {
+                            Ok(repaired) if repaired.is_empty() => println!("✅ No orphaned rows found."),
+                            Ok(repaired) => {
+                                for (orphan, fix) in &repaired {
+                                    let action = match fix {
+                                        OrphanRepair::Deleted => "deleted".to_string(),
+                                        OrphanRepair::Reassigned(language) => format!("moved to {}", language),
+                                    };
+                                    println!("  {} → {}", describe_orphan(orphan), action);
+                                }
+                                println!("✅ Repaired {} orphaned row(s).", repaired.len());
+                            }
+                            Err(e) => {
+                                eprintln!("❌ Error repairing the database: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    } else {
+                        match dao::find_orphans(database.conn()) {
+                            Ok(orphans) if orphans.is_empty() => println!("✅ No orphaned rows found."),
+                            Ok(orphans) => {
+                                for orphan in &orphans {
+                                    println!("  {}", describe_orphan(orphan));
+                                }
+                                println!(
+                                    "⚠️  Found {} orphaned row(s). Run 'clinotes db check --repair' to fix them.",
+                                    orphans.len()
+                                );
+                                std::process::exit(1);
+                            }
+                            Err(e) => {
+                                eprintln!("❌ Error checking the database: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Some(Commands::Watch { debounce_ms }) => {
+            let roots = dao::list_note_roots(database.conn()).unwrap_or_else(|e| {
+                eprintln!("❌ Error reading the note directories: {}", e);
+                std::process::exit(1);
+            });
+            if roots.is_empty() {
+                eprintln!("❌ No directories to watch. Add one with 'clinotes note scan <dir>'.");
+                std::process::exit(1);
+            }
+            let log = |line: String| println!("[{}] {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), line);
+
+            // Start the watch before catching up, so no change falls in between
+            let watcher = NoteWatcher::new(&roots, Duration::from_millis(debounce_ms)).unwrap_or_else(|e| {
+                eprintln!("❌ Error starting the watcher: {}", e);
+                std::process::exit(1);
+            });
+            match dao::sync_learning_notes(database.conn()) {
+                Ok(synced) => synced
+                    .iter()
+                    .filter_map(|(note, sync)| describe_note_sync(note, sync))
+                    .for_each(log),
+                Err(e) => eprintln!("❌ Error syncing notes: {}", e),
+            }
+            println!("👀 Watching {} director(ies) for note changes. Press Ctrl-C to stop.", roots.len());
+            for root in &roots {
+                println!("  {}", root.path);
+            }
+
+            loop {
+                let changes = watcher.wait_for_changes(None).unwrap_or_else(|e| {
+                    eprintln!("❌ {}", e);
+                    std::process::exit(1);
+                });
+                match watcher.apply(database.conn(), &changes) {
+                    Ok(report) => {
+                        for (note, sync) in &report.synced {
+                            if let Some(line) = describe_note_sync(note, sync) {
+                                log(line);
+                            }
+                        }
+                        for error in report.errors {
+                            log(format!("⚠️  {}", error));
+                        }
+                    }
+                    Err(e) => eprintln!("❌ Error updating notes: {}", e),
+                }
             }
         }
         None => {
@@ -266,18 +1658,18 @@ fn main() {
             println!(" ██████ ███████ ██ ██   ████  ██████     ██    ███████ ███████ ");
             println!("                                                                ");
             println!("                                                                ");
-            println!("");
+            println!();
             println!("Welcome to CliNotes - AI-Powered Journaling!");
-            println!("");
+            println!();
             println!("🤖 AI Journal Features:");
             println!("[1] Add Journal Entry (with AI sentiment analysis & auto-tagging)");
             println!("[2] AI Summary (weekly/monthly insights)");
             println!("[3] AI Insights (ask questions about your entries)");
             println!("[4] View Learning Notes (Latest 3 entries)");
-            println!("[5] View Code Snippets (Last 5 entries)");
+            println!("[5] View Code Snippets (Last 5 entries: 'snippet list --limit 5')");
             println!("[6] Add new Code Snippet");
             println!("[7] Exit");
-            println!("");
+            println!();
             println!("💡 Try: 'cargo run -- journal add \"Today I learned Rust!\"'");
             println!("💡 Try: 'cargo run -- journal summarize --period week'");
             println!("💡 Try: 'cargo run -- journal insights \"How do I feel about coding?\"'");
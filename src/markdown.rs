@@ -0,0 +1,172 @@
+use crate::models::{NoteHeading, NoteLinkKind};
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use sha2::{Digest, Sha256};
+
+/// What gets indexed from a markdown note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownSummary {
+    /// Text of the first heading, if the note has one.
+    pub title: Option<String>,
+    pub headings: Vec<NoteHeading>,
+    /// Words of text, not counting code blocks and front matter.
+    pub word_count: usize,
+    pub content_hash: String,
+    /// Links to other notes, in order and without duplicates.
+    pub links: Vec<LinkRef>,
+}
+
+/// A link to another note, as written in the note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRef {
+    pub kind: NoteLinkKind,
+    /// The page of a `[[wikilink]]`, or the relative path of a markdown link,
+    /// without any `#heading`.
+    pub target: String,
+}
+
+/// Hex SHA-256 of `content`, used to tell when a note changed or moved.
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn summarize(content: &str) -> MarkdownSummary {
+    let mut headings: Vec<NoteHeading> = Vec::new();
+    let mut heading: Option<NoteHeading> = None;
+    let mut text = String::new();
+    let mut in_code_block = false;
+    let mut in_metadata = false;
+    let mut links: Vec<LinkRef> = Vec::new();
+
+    // Without the metadata option, YAML front matter would read as a thematic break and a heading
+    let options = Options::ENABLE_YAML_STYLE_METADATA_BLOCKS | Options::ENABLE_WIKILINKS;
+    for event in Parser::new_ext(content, options) {
+        match event {
+            Event::Start(Tag::Link { link_type, dest_url, .. }) => {
+                if let Some(link) = note_link(link_type, &dest_url) {
+                    if !links.contains(&link) {
+                        links.push(link);
+                    }
+                }
+            }
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some(NoteHeading {
+                    level: level as u8,
+                    text: String::new(),
+                })
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(mut done) = heading.take() {
+                    done.text = done.text.trim().to_string();
+                    if !done.text.is_empty() {
+                        headings.push(done);
+                    }
+                }
+                text.push(' ');
+            }
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::MetadataBlock(_)) => in_metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+            Event::Text(fragment) | Event::Code(fragment) => {
+                if let Some(heading) = heading.as_mut() {
+                    heading.text.push_str(&fragment);
+                }
+                if !in_code_block && !in_metadata {
+                    text.push_str(&fragment);
+                }
+            }
+            // Inline markup splits words into several text events, block ends and breaks do not
+            Event::End(TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link | TagEnd::Image) => {}
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    MarkdownSummary {
+        title: headings.first().map(|heading| heading.text.clone()),
+        headings,
+        word_count: text.split_whitespace().count(),
+        content_hash: content_hash(content),
+        links,
+    }
+}
+
+/// The note a link points to, if it is a wikilink or a relative link to a markdown file.
+fn note_link(link_type: LinkType, destination: &str) -> Option<LinkRef> {
+    let without_fragment = |text: &str| text.split('#').next().unwrap_or_default().trim().to_string();
+    if let LinkType::WikiLink { .. } = link_type {
+        let target = without_fragment(destination);
+        return (!target.is_empty()).then_some(LinkRef {
+            kind: NoteLinkKind::Wiki,
+            target,
+        });
+    }
+
+    let path = without_fragment(destination.split('?').next().unwrap_or_default());
+    let has_scheme = path.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    let lowercase = path.to_lowercase();
+    let is_markdown = lowercase.ends_with(".md") || lowercase.ends_with(".markdown");
+    (is_markdown && !has_scheme && !path.starts_with('/')).then(|| LinkRef {
+        kind: NoteLinkKind::Markdown,
+        target: path.replace("%20", " "),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(
+            "---\ntitle: ignored\n---\n\nIntro before the title.\n\n# Git `rebase` tips\n\nSquash **fix**ups\nbefore pushing.\n\n```sh\ngit rebase -i HEAD~3\n```\n\nSetext heading\n--------------\n",
+        );
+        assert_eq!(summary.title.as_deref(), Some("Git rebase tips"));
+        let headings: Vec<(u8, &str)> = summary.headings.iter().map(|h| (h.level, h.text.as_str())).collect();
+        assert_eq!(headings, vec![(1, "Git rebase tips"), (2, "Setext heading")]);
+        // Intro before the title. / Git rebase tips / Squash fixups before pushing. / Setext heading
+        assert_eq!(summary.word_count, 4 + 3 + 4 + 2);
+    }
+
+    #[test]
+    fn test_note_without_headings() {
+        let summary = summarize("just some words\n");
+        assert_eq!(summary.title, None);
+        assert!(summary.headings.is_empty());
+        assert_eq!(summary.word_count, 3);
+        assert_eq!(summarize("").word_count, 0);
+    }
+
+    #[test]
+    fn test_links() {
+        let summary = summarize(
+            "# Rust\n\nSee [[Ownership]], [[borrowing#rules|the rules]] and [[Ownership]] again.\n\n\
+             Also [lifetimes](../rust/Life%20times.md#elision), [the book](https://doc.rust-lang.org/book/intro.md),\n\
+             [home](/home/me/index.md), [a picture](diagram.png) and [top](#rust).\n\n\
+             ```md\n[[Not a link]]\n```\n",
+        );
+        let links: Vec<(NoteLinkKind, &str)> = summary.links.iter().map(|l| (l.kind, l.target.as_str())).collect();
+        assert_eq!(
+            links,
+            vec![
+                (NoteLinkKind::Wiki, "Ownership"),
+                (NoteLinkKind::Wiki, "borrowing"),
+                (NoteLinkKind::Markdown, "../rust/Life times.md"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(
+            content_hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_ne!(content_hash("# a\n"), content_hash("# a \n"));
+    }
+}
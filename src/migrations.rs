@@ -0,0 +1,139 @@
+use rusqlite::{Connection, ErrorCode, Result};
+
+/// A single schema change. Migrations are applied in `version` order and the
+/// database's `PRAGMA user_version` records the last one that was applied.
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// All schema migrations, embedded in the binary. Append new ones at the end,
+/// never edit one that has already shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../sql/migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "journal_fts",
+        sql: include_str!("../sql/migrations/0002_journal_fts.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "tags",
+        sql: include_str!("../sql/migrations/0003_tags.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "verbatim_snippets",
+        sql: include_str!("../sql/migrations/0004_verbatim_snippets.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "language_aliases",
+        sql: include_str!("../sql/migrations/0005_language_aliases.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "shell_language",
+        sql: include_str!("../sql/migrations/0006_shell_language.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "snippet_metadata",
+        sql: include_str!("../sql/migrations/0007_snippet_metadata.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "snippet_parameters",
+        sql: include_str!("../sql/migrations/0008_snippet_parameters.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "snippet_runs",
+        sql: include_str!("../sql/migrations/0009_snippet_runs.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "note_content",
+        sql: include_str!("../sql/migrations/0010_note_content.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "learning_note_lookups",
+        sql: include_str!("../sql/migrations/0011_learning_note_lookups.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "note_sync",
+        sql: include_str!("../sql/migrations/0012_note_sync.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "note_links",
+        sql: include_str!("../sql/migrations/0013_note_links.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "shell_languages",
+        sql: include_str!("../sql/migrations/0014_shell_languages.sql"),
+    },
+];
+
+/// The schema version this build of cliNotes expects.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub fn current_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Migrations that have not been applied to this database yet.
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    let current = current_version(conn)?;
+    Ok(MIGRATIONS.iter().filter(|m| m.version > current).collect())
+}
+
+/// Applies every pending migration, each in its own transaction, and returns
+/// the versions that were applied.
+pub fn migrate(conn: &Connection) -> Result<Vec<u32>> {
+    let current = current_version(conn)?;
+    if current > latest_version() {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error {
+                code: ErrorCode::Unknown,
+                extended_code: 1,
+            },
+            Some(format!(
+                "Database schema version {} is newer than the latest known version {}. Please upgrade cliNotes.",
+                current,
+                latest_version()
+            )),
+        ));
+    }
+
+    let mut applied = Vec::new();
+    for migration in pending_migrations(conn)? {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(migration.sql).map_err(|e| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error {
+                    code: ErrorCode::Unknown,
+                    extended_code: 1,
+                },
+                Some(format!(
+                    "Migration {} ({}) failed: {}",
+                    migration.version, migration.name, e
+                )),
+            )
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
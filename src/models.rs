@@ -1,4 +1,5 @@
 use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
 #[derive(Debug)]
 pub struct JournalEntry {
     pub id: i64,
@@ -7,6 +8,8 @@ pub struct JournalEntry {
     pub tags: Option<String>,
     pub sentiment: Option<String>,
     pub ai_tags: Option<String>,
+    /// Normalized tags attached to the entry, both user-supplied and AI-generated.
+    pub tag_list: Vec<Tag>,
 }
 
 impl JournalEntry {
@@ -18,10 +21,59 @@ impl JournalEntry {
             tags,
             sentiment: None,
             ai_tags: None,
+            tag_list: Vec::new(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagSource {
+    User,
+    Ai,
+}
+
+impl TagSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagSource::User => "user",
+            TagSource::Ai => "ai",
+        }
+    }
+
+    pub fn parse(source: &str) -> Option<Self> {
+        match source {
+            "user" => Some(TagSource::User),
+            "ai" => Some(TagSource::Ai),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub source: TagSource,
+}
+
+/// A tag together with how many journal entries use it, per source.
+#[derive(Debug, PartialEq)]
+pub struct TagUsage {
+    pub id: i64,
+    pub name: String,
+    pub user_count: usize,
+    pub ai_count: usize,
+}
+
+/// A journal entry matched by a full-text search.
+#[derive(Debug)]
+pub struct JournalSearchResult {
+    pub entry: JournalEntry,
+    /// Excerpt around the matched terms, with matches wrapped in the requested markers.
+    pub snippet: String,
+    /// BM25 rank reported by SQLite; lower is more relevant.
+    pub rank: f64,
+}
 
 #[derive(Debug)]
 pub struct JournalSummary {
@@ -29,14 +81,18 @@ pub struct JournalSummary {
     pub positive_count: usize,
     pub negative_count: usize,
     pub neutral_count: usize,
-    pub common_topics: String,
+    /// Topics (user and AI tags) with the number of entries mentioning them, most frequent first.
+    pub common_topics: Vec<(String, usize)>,
 }
 
 
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Language {
     pub id: i64,
     pub name: String,
+    /// Alternative names that resolve to this language, e.g. `py` for `python`.
+    pub aliases: Vec<String>,
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
 }
@@ -47,20 +103,211 @@ pub struct CodeSnippet {
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
     pub language_id: i64,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Where the snippet came from, e.g. a Stack Overflow answer.
+    pub source_url: Option<String>,
+    /// Comma-separated tags.
+    pub tags: Option<String>,
+    /// The `{{placeholders}}` in `full_code`, detected by the DAO when the snippet is saved.
+    pub parameters: Vec<SnippetParameter>,
+}
+
+/// A `{{name}}` or `{{name:default}}` placeholder in a snippet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetParameter {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+impl CodeSnippet {
+    pub fn new(full_code: String) -> Self {
+        CodeSnippet {
+            id: 0,
+            full_code,
+            created_at: Local::now(),
+            updated_at: Local::now(),
+            language_id: 0,
+            title: None,
+            description: None,
+            source_url: None,
+            tags: None,
+            parameters: Vec::new(),
+        }
+    }
+}
+
+/// One execution of a shell snippet with `snippet exec`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetRun {
+    pub id: i64,
+    pub snippet_id: i64,
+    /// The command as run, placeholders filled in.
+    pub command: String,
+    /// `None` when the process was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub duration_ms: i64,
+    pub ran_at: DateTime<Local>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct LearningNote {
     pub id: i64,
     pub file_path: String,
     pub file_name: String,
+    /// Text of the note's first heading.
+    pub title: Option<String>,
+    /// Hex SHA-256 of the file when it was last indexed. `None` for notes added before
+    /// notes were indexed.
+    pub content_hash: Option<String>,
+    pub word_count: i64,
+    pub headings: Vec<NoteHeading>,
+    /// When `note sync` last found the file gone, `None` while it exists.
+    pub missing_since: Option<DateTime<Local>>,
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
 }
 
+/// What importing a markdown file found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteImport {
+    /// The file was registered as a new note.
+    Added(i64),
+    /// The file was already a note and its content changed, so it was re-indexed.
+    Changed(i64),
+    /// The file was already a note with the same content.
+    Unchanged(i64),
+    /// Another note has the same content, e.g. a copy of the file.
+    Duplicate(i64),
+}
+
+/// What `note sync` or `watch` found for a note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteSync {
+    /// A new file under a watched directory was registered.
+    Added,
+    Unchanged,
+    /// The file changed and was re-indexed.
+    Updated,
+    /// The file was found under a scanned directory by its content. Holds the old path.
+    Moved(String),
+    /// The file is gone and could not be found elsewhere.
+    Missing,
+    /// The file of a note flagged missing is back at its path.
+    Restored,
+    /// The file is there but could not be read, e.g. it is not UTF-8. Holds the error.
+    Unreadable(String),
+    /// The note was added with a path relative to another directory than the current one,
+    /// so its file cannot be found.
+    Unresolved,
+}
+
+/// A directory added with `note scan`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteRoot {
+    pub id: i64,
+    pub path: String,
+    pub glob: String,
+    pub excludes: Vec<String>,
+    pub scanned_at: DateTime<Local>,
+}
+
+/// How one note links to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteLinkKind {
+    /// `[[page]]`, resolved by file name or title.
+    Wiki,
+    /// `[text](relative/path.md)`, resolved relative to the linking note.
+    Markdown,
+}
+
+impl NoteLinkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteLinkKind::Wiki => "wiki",
+            NoteLinkKind::Markdown => "markdown",
+        }
+    }
+}
+
+/// A link from a learning note, resolved against the notes' current files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteLink {
+    pub source_note_id: i64,
+    pub kind: NoteLinkKind,
+    /// The link as written, without any `#heading`.
+    pub target: String,
+    /// The linked note, `None` for a broken link.
+    pub target_note_id: Option<i64>,
+}
+
+impl NoteLink {
+    /// The link as written in markdown, e.g. `[[page]]` or `../notes/page.md`.
+    pub fn written(&self) -> String {
+        match self.kind {
+            NoteLinkKind::Wiki => format!("[[{}]]", self.target),
+            NoteLinkKind::Markdown => self.target.clone(),
+        }
+    }
+}
+
+/// A markdown heading, `level` 1 for `#` to 6 for `######`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteHeading {
+    pub level: u8,
+    pub text: String,
+}
+
+/// A learning note matched by a full-text search.
+#[derive(Debug)]
+pub struct NoteSearchResult {
+    pub note: LearningNote,
+    /// Excerpt around the matched terms, with matches wrapped in the requested markers.
+    pub snippet: String,
+    /// BM25 rank reported by SQLite; lower is more relevant.
+    pub rank: f64,
+}
+
+/// Something a snippet can be linked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkTarget {
+    Journal(i64),
+    Note(i64),
+}
+
+/// A link recording that a snippet was used in a journal entry or learning note.
+#[derive(Debug, Clone, PartialEq)]
 pub struct SnippetUsed {
     pub id: i64,
     pub snippet_id: i64,
-    pub description: String,
+    pub description: Option<String>,
     pub learning_note_id: Option<i64>,
     pub journal_entry_id: Option<i64>,
 }
+
+impl SnippetUsed {
+    pub fn target(&self) -> Option<LinkTarget> {
+        match (self.journal_entry_id, self.learning_note_id) {
+            (Some(id), _) => Some(LinkTarget::Journal(id)),
+            (None, Some(id)) => Some(LinkTarget::Note(id)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// A row referencing a parent row that does not exist, as reported by `PRAGMA foreign_key_check`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanRow {
+    pub table: String,
+    pub rowid: i64,
+    /// The table the missing parent row belongs in.
+    pub parent: String,
+}
+
+/// How `db check --repair` fixed an orphan row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrphanRepair {
+    Deleted,
+    /// A snippet whose language was missing was moved to this language.
+    Reassigned(String),
+}
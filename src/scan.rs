@@ -0,0 +1,206 @@
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
+use ignore::WalkBuilder;
+use std::path::{Path, PathBuf};
+
+/// Files matched by `note scan` when no `--glob` is given.
+pub const DEFAULT_GLOB: &str = "**/*.md";
+
+/// The files found under a directory, and the entries that could not be read.
+#[derive(Debug, Default)]
+pub struct FileScan {
+    pub files: Vec<PathBuf>,
+    pub errors: Vec<String>,
+}
+
+/// Which files under a directory are notes: those whose path relative to the directory
+/// matches a glob, minus hidden files, files ignored by `.gitignore` / `.ignore` files,
+/// and paths or names matching one of the excludes.
+#[derive(Debug, Clone)]
+pub struct FileFilter {
+    root: PathBuf,
+    include: GlobMatcher,
+    exclude: GlobSet,
+}
+
+impl FileFilter {
+    pub fn new(root: &Path, glob: &str, excludes: &[String]) -> Result<Self, String> {
+        let include = Glob::new(glob)
+            .map_err(|e| format!("Invalid glob '{}': {}", glob, e))?
+            .compile_matcher();
+        let mut exclude = GlobSetBuilder::new();
+        for glob in excludes {
+            exclude.add(Glob::new(glob).map_err(|e| format!("Invalid glob '{}': {}", glob, e))?);
+        }
+        Ok(FileFilter {
+            root: root.to_path_buf(),
+            include,
+            exclude: exclude.build().map_err(|e| e.to_string())?,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        self.exclude.is_match(relative) || path.file_name().is_some_and(|name| self.exclude.is_match(name))
+    }
+
+    fn is_included(&self, path: &Path) -> bool {
+        self.include.is_match(path.strip_prefix(&self.root).unwrap_or(path))
+    }
+
+    /// Whether `path` is a note file that a scan of the root would find. Unlike a scan,
+    /// this also works for paths that no longer exist.
+    pub fn matches(&self, path: &Path) -> bool {
+        path != self.root && !path.is_dir() && self.is_included(path) && self.admits(path)
+    }
+
+    /// Whether `path` is the root or under it, without it or a directory it is in
+    /// being hidden, ignored or excluded.
+    fn admits(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let mut current = self.root.clone();
+        for component in relative.components() {
+            let name = component.as_os_str();
+            if name.to_string_lossy().starts_with('.') || is_ignored_in(&current, path) {
+                return false;
+            }
+            current.push(name);
+            if self.is_excluded(&current) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Whether the ignore files of `dir` ignore `path`, which is inside it.
+fn is_ignored_in(dir: &Path, path: &Path) -> bool {
+    [".gitignore", ".ignore"].iter().any(|name| {
+        let file = dir.join(name);
+        if !file.is_file() {
+            return false;
+        }
+        let (ignore, _) = Gitignore::new(&file);
+        ignore.matched_path_or_any_parents(path, false).is_ignore()
+    })
+}
+
+/// Walks `root` for the files matched by `glob` and not excluded, as described on
+/// [`FileFilter`]. Files are returned sorted.
+pub fn find_files(root: &Path, glob: &str, excludes: &[String]) -> Result<FileScan, String> {
+    if !root.is_dir() {
+        return Err(format!("'{}' is not a directory", root.display()));
+    }
+    let filter = FileFilter::new(root, glob, excludes)?;
+    Ok(walk(&filter, root))
+}
+
+/// Walks `dir`, which is `filter`'s root or a directory under it, for the files `filter` matches.
+pub fn walk(filter: &FileFilter, dir: &Path) -> FileScan {
+    if !filter.admits(dir) {
+        return FileScan::default();
+    }
+    let walk_filter = filter.clone();
+    let walker = WalkBuilder::new(dir)
+        // Respect .gitignore even in directories that are not git repositories
+        .require_git(false)
+        .filter_entry(move |entry| entry.depth() == 0 || !walk_filter.is_excluded(entry.path()))
+        .build();
+
+    let mut scan = FileScan::default();
+    for entry in walker {
+        match entry {
+            Ok(entry) => {
+                let is_file = entry.file_type().is_some_and(|file_type| file_type.is_file());
+                if is_file && filter.is_included(entry.path()) {
+                    scan.files.push(entry.into_path());
+                }
+            }
+            Err(e) => scan.errors.push(e.to_string()),
+        }
+    }
+    scan.files.sort();
+    scan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn touch(root: &Path, path: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "# note\n").unwrap();
+    }
+
+    fn relative_files(root: &Path, glob: &str, excludes: &[&str]) -> Vec<String> {
+        let excludes: Vec<String> = excludes.iter().map(|e| e.to_string()).collect();
+        find_files(root, glob, &excludes)
+            .unwrap()
+            .files
+            .iter()
+            .map(|file| file.strip_prefix(root).unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_find_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for path in [
+            "a.md",
+            "notes/b.md",
+            "notes/c.txt",
+            "notes/drafts/d.md",
+            "build/e.md",
+            ".hidden/f.md",
+            "node_modules/pkg/g.md",
+        ] {
+            touch(root, path);
+        }
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+
+        assert_eq!(
+            relative_files(root, DEFAULT_GLOB, &[]),
+            vec!["a.md", "node_modules/pkg/g.md", "notes/b.md", "notes/drafts/d.md"]
+        );
+        assert_eq!(
+            relative_files(root, DEFAULT_GLOB, &["node_modules", "notes/drafts/**"]),
+            vec!["a.md", "notes/b.md"]
+        );
+        assert_eq!(relative_files(root, "notes/*.txt", &[]), vec!["notes/c.txt"]);
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        touch(root, "notes/b.md");
+        fs::write(root.join("notes/.gitignore"), "drafts/\n").unwrap();
+        let filter = FileFilter::new(root, DEFAULT_GLOB, &["vendor".to_string()]).unwrap();
+
+        assert!(filter.matches(&root.join("notes/b.md")));
+        // Paths that are gone still match, so deleted notes can be recognized
+        assert!(filter.matches(&root.join("notes/deleted.md")));
+        assert!(!filter.matches(&root.join("notes")));
+        assert!(!filter.matches(&root.join("notes/c.txt")));
+        assert!(!filter.matches(&root.join("notes/drafts/d.md")));
+        assert!(!filter.matches(&root.join("vendor/lib.md")));
+        assert!(!filter.matches(&root.join(".hidden/f.md")));
+        assert!(!filter.matches(Path::new("/elsewhere/a.md")));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(find_files(dir.path(), "[", &[]).is_err());
+        assert!(find_files(&dir.path().join("missing"), DEFAULT_GLOB, &[]).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::models::SnippetParameter;
+use std::collections::HashMap;
+
+/// A `{{name}}` or `{{name:default}}` placeholder found in a template.
+struct Placeholder<'a> {
+    /// Byte range of the whole placeholder, braces included.
+    start: usize,
+    end: usize,
+    name: &'a str,
+    default: Option<&'a str>,
+}
+
+/// Finds the placeholders of `template` in order. Names start with a letter or `_` and
+/// contain letters, digits, `_` and `-`, so things like Go's `{{.Field}}` are left alone.
+fn placeholders(template: &str) -> Vec<Placeholder<'_>> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = template[offset..].find("{{") {
+        let start = offset + open;
+        let Some(close) = template[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + close + 2;
+        let inner = &template[start + 2..end - 2];
+        let (name, default) = match inner.split_once(':') {
+            Some((name, default)) => (name.trim(), Some(default)),
+            None => (inner.trim(), None),
+        };
+        if is_valid_name(name) {
+            found.push(Placeholder { start, end, name, default });
+            offset = end;
+        } else {
+            offset = start + 2;
+        }
+    }
+    found
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// The distinct parameters of `template` in order of appearance. When a parameter
+/// appears several times, the first default given for it is used.
+pub fn parameters(template: &str) -> Vec<SnippetParameter> {
+    let mut parameters: Vec<SnippetParameter> = Vec::new();
+    for placeholder in placeholders(template) {
+        match parameters.iter_mut().find(|p| p.name == placeholder.name) {
+            Some(existing) => {
+                if existing.default.is_none() {
+                    existing.default = placeholder.default.map(String::from);
+                }
+            }
+            None => parameters.push(SnippetParameter {
+                name: placeholder.name.to_string(),
+                default: placeholder.default.map(String::from),
+            }),
+        }
+    }
+    parameters
+}
+
+/// Substitutes every placeholder with its value from `values`, falling back to its
+/// default. Fails listing the parameters that have neither.
+pub fn render(template: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    let defaults: HashMap<String, Option<String>> = parameters(template)
+        .into_iter()
+        .map(|parameter| (parameter.name, parameter.default))
+        .collect();
+    let missing: Vec<String> = parameters(template)
+        .into_iter()
+        .filter(|parameter| parameter.default.is_none() && !values.contains_key(&parameter.name))
+        .map(|parameter| parameter.name)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing values for: {}", missing.join(", ")));
+    }
+
+    let mut rendered = String::with_capacity(template.len());
+    let mut last = 0;
+    for placeholder in placeholders(template) {
+        rendered.push_str(&template[last..placeholder.start]);
+        let value = values
+            .get(placeholder.name)
+            .map(String::as_str)
+            .or(defaults[placeholder.name].as_deref())
+            .unwrap_or_default();
+        rendered.push_str(value);
+        last = placeholder.end;
+    }
+    rendered.push_str(&template[last..]);
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parameters() {
+        let params = parameters("docker run -p {{port:8080}}:80 {{ image }} && echo {{port}} {{url:http://x:1}}");
+        let summary: Vec<(&str, Option<&str>)> =
+            params.iter().map(|p| (p.name.as_str(), p.default.as_deref())).collect();
+        assert_eq!(
+            summary,
+            vec![("port", Some("8080")), ("image", None), ("url", Some("http://x:1"))]
+        );
+    }
+
+    #[test]
+    fn test_non_placeholders_are_kept() {
+        let template = "{{.Name}} {{}} {{ 1x }} {{unclosed";
+        assert!(parameters(template).is_empty());
+        assert_eq!(render(template, &HashMap::new()).unwrap(), template);
+    }
+
+    #[test]
+    fn test_render() {
+        let template = "docker run -p {{port:8080}}:80 {{image}} # {{port}}";
+        assert_eq!(
+            render(template, &values(&[("image", "nginx")])).unwrap(),
+            "docker run -p 8080:80 nginx # 8080"
+        );
+        assert_eq!(
+            render(template, &values(&[("image", "nginx"), ("port", "3000")])).unwrap(),
+            "docker run -p 3000:80 nginx # 3000"
+        );
+        assert_eq!(render(template, &HashMap::new()).unwrap_err(), "Missing values for: image");
+    }
+}
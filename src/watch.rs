@@ -0,0 +1,280 @@
+use crate::dao::{self, DaoError};
+use crate::models::{LearningNote, NoteImport, NoteRoot, NoteSync};
+use crate::scan::{self, FileFilter};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::Connection;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// How long the watched directories must stay quiet before a burst of changes is applied.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// The paths touched by a burst of filesystem events.
+#[derive(Debug, Default, PartialEq)]
+pub struct Changes {
+    pub paths: Vec<PathBuf>,
+    /// Events were lost, e.g. because the inotify queue overflowed, so every
+    /// watched directory has to be checked.
+    pub rescan: bool,
+}
+
+/// What applying a batch of changes did to the notes.
+#[derive(Debug, Default)]
+pub struct WatchReport {
+    pub synced: Vec<(LearningNote, NoteSync)>,
+    pub errors: Vec<String>,
+}
+
+/// Watches the directories added with `note scan` (inotify on Linux) for note files
+/// being created, edited, renamed and deleted.
+pub struct NoteWatcher {
+    // Events stop when the watcher is dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    filters: Vec<FileFilter>,
+    debounce: Duration,
+}
+
+impl NoteWatcher {
+    pub fn new(roots: &[NoteRoot], debounce: Duration) -> Result<Self, String> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(|e| e.to_string())?;
+        let mut filters = Vec::new();
+        for root in roots {
+            let path = Path::new(&root.path);
+            watcher
+                .watch(path, RecursiveMode::Recursive)
+                .map_err(|e| format!("Cannot watch {}: {}", root.path, e))?;
+            filters.push(FileFilter::new(path, &root.glob, &root.excludes)?);
+        }
+        Ok(NoteWatcher {
+            _watcher: watcher,
+            events,
+            filters,
+            debounce,
+        })
+    }
+
+    /// Blocks until something changes, or at most `timeout`, then keeps collecting
+    /// events until none arrived for the debounce period. Returns no changes on timeout.
+    pub fn wait_for_changes(&self, timeout: Option<Duration>) -> Result<Changes, String> {
+        let stopped = || "The filesystem watcher stopped".to_string();
+        let first = match timeout {
+            Some(timeout) => match self.events.recv_timeout(timeout) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => return Ok(Changes::default()),
+                Err(RecvTimeoutError::Disconnected) => return Err(stopped()),
+            },
+            None => self.events.recv().map_err(|_| stopped())?,
+        };
+
+        let mut paths = BTreeSet::new();
+        let mut rescan = false;
+        let mut add = |event: notify::Result<Event>| match event {
+            Ok(event) => {
+                rescan |= event.need_rescan();
+                paths.extend(event.paths);
+            }
+            Err(_) => rescan = true,
+        };
+        add(first);
+        while let Ok(event) = self.events.recv_timeout(self.debounce) {
+            add(event);
+        }
+        Ok(Changes {
+            paths: paths.into_iter().collect(),
+            rescan,
+        })
+    }
+
+    /// Updates the notes for `changes`: new note files are added, edited ones re-indexed,
+    /// renamed ones relinked and deleted ones flagged missing. Directories are handled as
+    /// all the note files in them.
+    pub fn apply(&self, conn: &Connection, changes: &Changes) -> Result<WatchReport, DaoError> {
+        let mut changed: Vec<PathBuf> = changes.paths.clone();
+        if changes.rescan {
+            changed.extend(self.filters.iter().map(|filter| filter.root().to_path_buf()));
+        }
+
+        let mut report = WatchReport::default();
+        let mut files = BTreeSet::new();
+        for path in &changed {
+            if path.is_dir() {
+                for filter in self.filters.iter().filter(|filter| path.starts_with(filter.root())) {
+                    let found = scan::walk(filter, path);
+                    files.extend(found.files);
+                    report.errors.extend(found.errors);
+                }
+            } else if path.is_file() && self.filters.iter().any(|filter| filter.matches(path)) {
+                files.insert(path.clone());
+            }
+        }
+
+        // Notes whose file is gone, including the notes of a removed directory
+        let mut vanished: Vec<LearningNote> = dao::list_learning_notes(conn)?
+            .into_iter()
+            .filter(|note| {
+                let file = Path::new(&note.file_path);
+                note.missing_since.is_none() && !file.exists() && changed.iter().any(|path| file.starts_with(path))
+            })
+            .collect();
+
+        for file in files {
+            let note_id = match dao::import_learning_note(conn, &file) {
+                Ok(NoteImport::Added(id)) => {
+                    report.synced.push((read_note(conn, id)?, NoteSync::Added));
+                    continue;
+                }
+                Ok(NoteImport::Changed(id)) => {
+                    report.synced.push((read_note(conn, id)?, NoteSync::Updated));
+                    id
+                }
+                Ok(NoteImport::Unchanged(id)) => id,
+                Ok(NoteImport::Duplicate(id)) => {
+                    // A copy of a note that still exists is skipped, like `note scan` does
+                    let note = read_note(conn, id)?;
+                    if Path::new(&note.file_path).exists() {
+                        continue;
+                    }
+                    dao::relink_learning_note(conn, id, &file.canonicalize().unwrap_or(file))?;
+                    vanished.retain(|vanished| vanished.id != id);
+                    report.synced.push((read_note(conn, id)?, NoteSync::Moved(note.file_path)));
+                    continue;
+                }
+                // The file is gone again or cannot be read
+                Err(DaoError::NotFound(message)) | Err(DaoError::InvalidInput(message)) => {
+                    report.errors.push(message);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let note = read_note(conn, note_id)?;
+            if note.missing_since.is_some() {
+                dao::relink_learning_note(conn, note_id, Path::new(&note.file_path))?;
+                report.synced.retain(|(synced, _)| synced.id != note_id);
+                report.synced.push((read_note(conn, note_id)?, NoteSync::Restored));
+            }
+        }
+
+        for note in vanished {
+            dao::mark_learning_note_missing(conn, note.id)?;
+            report.synced.push((read_note(conn, note.id)?, NoteSync::Missing));
+        }
+        Ok(report)
+    }
+}
+
+fn read_note(conn: &Connection, note_id: i64) -> Result<LearningNote, DaoError> {
+    dao::read_learning_note(conn, note_id)?.ok_or_else(|| DaoError::NotFound(format!("Note #{}", note_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::fs;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        root: PathBuf,
+        db: Database,
+        watcher: NoteWatcher,
+    }
+
+    fn fixture() -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let db = Database::new(":memory:").unwrap();
+        db.initialize().unwrap();
+        dao::record_note_root(db.conn(), &root, scan::DEFAULT_GLOB, &["drafts".to_string()]).unwrap();
+        let roots = dao::list_note_roots(db.conn()).unwrap();
+        let watcher = NoteWatcher::new(&roots, Duration::from_millis(200)).unwrap();
+        Fixture {
+            _dir: dir,
+            root,
+            db,
+            watcher,
+        }
+    }
+
+    impl Fixture {
+        /// Waits for the next burst of changes and applies it.
+        fn next(&self) -> Vec<(String, NoteSync)> {
+            let changes = self.watcher.wait_for_changes(Some(TIMEOUT)).unwrap();
+            assert!(!changes.paths.is_empty(), "no filesystem events within {:?}", TIMEOUT);
+            let report = self.watcher.apply(self.db.conn(), &changes).unwrap();
+            assert!(report.errors.is_empty(), "{:?}", report.errors);
+            report
+                .synced
+                .into_iter()
+                .map(|(note, sync)| (note.file_name, sync))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_watch_note_lifecycle() {
+        let fixture = fixture();
+        let root = &fixture.root;
+
+        // A burst of writes is applied once
+        fs::write(root.join("a.md"), "# A\n").unwrap();
+        fs::write(root.join("a.md"), "# A\n\nMore.\n").unwrap();
+        fs::write(root.join("notes.txt"), "not a note").unwrap();
+        assert_eq!(fixture.next(), vec![("a.md".to_string(), NoteSync::Added)]);
+        let note = dao::find_learning_note_by_path(fixture.db.conn(), &root.join("a.md").to_string_lossy())
+            .unwrap()
+            .unwrap();
+        assert_eq!(note.word_count, 2);
+
+        fs::write(root.join("a.md"), "# A\n\nEven more.\n").unwrap();
+        assert_eq!(fixture.next(), vec![("a.md".to_string(), NoteSync::Updated)]);
+
+        fs::rename(root.join("a.md"), root.join("b.md")).unwrap();
+        let old_path = root.join("a.md").to_string_lossy().into_owned();
+        assert_eq!(fixture.next(), vec![("b.md".to_string(), NoteSync::Moved(old_path))]);
+
+        fs::remove_file(root.join("b.md")).unwrap();
+        assert_eq!(fixture.next(), vec![("b.md".to_string(), NoteSync::Missing)]);
+
+        fs::write(root.join("b.md"), "# A\n\nEven more.\n").unwrap();
+        assert_eq!(fixture.next(), vec![("b.md".to_string(), NoteSync::Restored)]);
+    }
+
+    #[test]
+    fn test_watch_directories() {
+        let fixture = fixture();
+        let root = &fixture.root;
+        let outside = tempfile::tempdir().unwrap();
+        fs::create_dir(outside.path().join("guide")).unwrap();
+        fs::write(outside.path().join("guide/intro.md"), "# Intro\n").unwrap();
+        fs::create_dir(root.join("drafts")).unwrap();
+        fs::write(root.join("drafts/wip.md"), "# Work in progress\n").unwrap();
+        // Excluded files are not added
+        assert!(fixture.next().is_empty());
+
+        // Files moved in with their directory produce no events of their own
+        fs::rename(outside.path().join("guide"), root.join("guide")).unwrap();
+        assert_eq!(fixture.next(), vec![("intro.md".to_string(), NoteSync::Added)]);
+
+        fs::remove_dir_all(root.join("guide")).unwrap();
+        assert_eq!(fixture.next(), vec![("intro.md".to_string(), NoteSync::Missing)]);
+    }
+
+    #[test]
+    fn test_rescan_applies_missed_changes() {
+        let fixture = fixture();
+        let changes = Changes {
+            paths: Vec::new(),
+            rescan: true,
+        };
+        fs::write(fixture.root.join("missed.md"), "# Missed\n").unwrap();
+        let report = fixture.watcher.apply(fixture.db.conn(), &changes).unwrap();
+        assert_eq!(report.synced.len(), 1);
+        assert_eq!(report.synced[0].1, NoteSync::Added);
+    }
+}
@@ -0,0 +1,120 @@
+use cli_notes::ai::{JournalAnalyzer, OpenAiAnalyzer};
+use cli_notes::config::AiConfig;
+use cli_notes::dao;
+use cli_notes::db;
+use cli_notes::models::JournalEntry;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+/// Starts a one-shot HTTP server that answers a single request with `status` and `body`.
+/// Returns the base URL and a handle yielding the raw request that was received.
+fn spawn_mock_server(status: u16, body: String) -> (String, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock server");
+    let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("Mock server did not receive a request");
+        let mut reader = BufReader::new(stream);
+
+        let mut request = String::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse().unwrap();
+            }
+            request.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut request_body = vec![0; content_length];
+        reader.read_exact(&mut request_body).unwrap();
+        request.push_str(&String::from_utf8(request_body).unwrap());
+
+        let response = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        reader.get_mut().write_all(response.as_bytes()).unwrap();
+        request
+    });
+
+    (base_url, handle)
+}
+
+fn completion_body(content: &str) -> String {
+    serde_json::json!({
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": content}}]
+    })
+    .to_string()
+}
+
+#[test]
+fn test_openai_analyzer_against_mock_server() {
+    let (base_url, server) = spawn_mock_server(
+        200,
+        completion_body("{\"sentiment\": \"negative\", \"tags\": [\"Rust\", \"borrow-checker\"]}"),
+    );
+
+    let analyzer =
+        OpenAiAnalyzer::new(&base_url, "test-model", Some("secret".to_string()), Duration::from_secs(5)).unwrap();
+    let analysis = analyzer.analyze("Fought the borrow checker all day").unwrap();
+
+    assert_eq!(analysis.sentiment, "negative");
+    assert_eq!(analysis.ai_tags, vec!["rust", "borrow-checker"]);
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("POST /v1/chat/completions "));
+    assert!(request.to_lowercase().contains("authorization: bearer secret"));
+    assert!(request.contains("\"model\":\"test-model\""));
+    assert!(request.contains("Fought the borrow checker all day"));
+}
+
+#[test]
+fn test_openai_analyzer_reports_http_errors() {
+    let (base_url, server) = spawn_mock_server(500, "{\"error\": \"boom\"}".to_string());
+
+    let analyzer = OpenAiAnalyzer::new(&base_url, "test-model", None, Duration::from_secs(5)).unwrap();
+    assert!(analyzer.analyze("Anything").is_err());
+
+    let request = server.join().unwrap();
+    assert!(!request.to_lowercase().contains("authorization:"));
+}
+
+#[test]
+fn test_journal_entry_with_configured_backend() {
+    let (base_url, server) = spawn_mock_server(
+        200,
+        completion_body("{\"sentiment\": \"positive\", \"tags\": [\"sqlite\"]}"),
+    );
+
+    let config: AiConfig = serde_json::from_str(&format!(
+        "{{\"backend\": \"openai\", \"base_url\": \"{}\", \"model\": \"test-model\"}}",
+        base_url
+    ))
+    .unwrap();
+    let analyzer = config.build_analyzer().unwrap();
+
+    let db = db::Database::new(":memory:").unwrap();
+    db.initialize().unwrap();
+
+    let journal_entry = JournalEntry::new(String::from("Wrote my first FTS query"), None);
+    let entry_id = dao::create_journal_entry_with_analyzer(db.conn(), &journal_entry, analyzer.as_ref()).unwrap();
+    server.join().unwrap();
+
+    let read_entry = dao::read_journal_entry(db.conn(), entry_id).unwrap().unwrap();
+    assert_eq!(read_entry.sentiment.as_deref(), Some("positive"));
+    assert_eq!(read_entry.ai_tags.as_deref(), Some("sqlite"));
+}
+
+#[test]
+fn test_default_config_uses_keyword_backend() {
+    let config: cli_notes::config::Config = serde_json::from_str("{}").unwrap();
+    assert_eq!(config.ai, AiConfig::Keyword);
+}
@@ -0,0 +1,543 @@
+use assert_cmd::Command;
+use tempfile::TempDir;
+
+/// Runs the cli_notes binary with its config directory (and so its database) inside `home`.
+fn cli(home: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("cli_notes").unwrap();
+    cmd.env("XDG_CONFIG_HOME", home.path()).env("HOME", home.path());
+    cmd
+}
+
+fn stdout_of(cmd: &mut Command) -> String {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_db_status_and_migrate() {
+    let home = TempDir::new().unwrap();
+
+    let status = stdout_of(cli(&home).args(["db", "status"]));
+    assert!(status.contains("Schema version: 0"));
+    assert!(status.contains("0001 initial_schema"));
+
+    let migrate = stdout_of(cli(&home).args(["db", "migrate"]));
+    assert!(migrate.contains("Applied migration 1"));
+
+    let status = stdout_of(cli(&home).args(["db", "status"]));
+    assert!(status.contains("No pending migrations"));
+}
+
+#[test]
+fn test_runs_outside_repo_root() {
+    // The schema is embedded in the binary, so no sql/ directory is needed next to the cwd
+    let home = TempDir::new().unwrap();
+    let cwd = TempDir::new().unwrap();
+
+    let output = stdout_of(
+        cli(&home)
+            .current_dir(cwd.path())
+            .args(["journal", "add", "Installed cliNotes and it just works"]),
+    );
+    assert!(output.contains("Journal entry created successfully with ID: 1"));
+    assert!(home.path().join("clinotes").join("clinotes.db").exists());
+    assert!(std::fs::read_dir(cwd.path()).unwrap().next().is_none());
+}
+
+#[test]
+fn test_summarize_period_conflicts_with_bounds() {
+    let home = TempDir::new().unwrap();
+    stdout_of(cli(&home).args(["journal", "summarize", "--since", "3d"]));
+    cli(&home)
+        .args(["journal", "summarize", "--period", "month", "--since", "3d"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_journal_list_show_edit_delete() {
+    let home = TempDir::new().unwrap();
+    stdout_of(cli(&home).args(["journal", "add", "First entry about Rust", "--tags", "rust"]));
+    stdout_of(cli(&home).args(["journal", "add", "Second entry"]));
+
+    let list = stdout_of(cli(&home).args(["journal", "list", "--per-page", "1"]));
+    assert!(list.contains("Second entry"));
+    assert!(!list.contains("First entry"));
+    assert!(list.contains("Page 1 of 2 (2 entries)"));
+
+    let edit = stdout_of(cli(&home).args(["journal", "edit", "1", "A great first entry", "--tags", "rust, cli"]));
+    assert!(edit.contains("Journal entry 1 updated"));
+
+    let show = stdout_of(cli(&home).args(["journal", "show", "1"]));
+    assert!(show.contains("A great first entry"));
+    assert!(show.contains("Sentiment: positive"));
+    assert!(show.contains("Tags: cli, rust"));
+
+    stdout_of(cli(&home).args(["journal", "delete", "1", "--yes"]));
+    let show = stdout_of(cli(&home).args(["journal", "show", "1"]));
+    assert!(show.contains("not found"));
+}
+
+/// Writes an executable shell script that stands in for `$EDITOR`.
+fn fake_editor(dir: &TempDir, script: &str) -> String {
+    stub_script(dir, "fake-editor.sh", script)
+}
+
+/// Writes an executable shell script into `dir` and returns its path.
+fn stub_script(dir: &TempDir, name: &str, script: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    let path = dir.path().join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_journal_add_from_stdin() {
+    let home = TempDir::new().unwrap();
+    let output = stdout_of(
+        cli(&home)
+            .args(["journal", "add", "-", "--tags", "meetings"])
+            .write_stdin("Standup notes\n- shipped search\n- 'quotes' survive\n"),
+    );
+    assert!(output.contains("created successfully with ID: 1"));
+
+    let show = stdout_of(cli(&home).args(["journal", "show", "1"]));
+    assert!(show.contains("Standup notes\n- shipped search\n- 'quotes' survive\n---"));
+    assert!(show.contains("Tags: meetings"));
+}
+
+#[test]
+fn test_journal_add_and_edit_in_editor() {
+    let home = TempDir::new().unwrap();
+    // Prepend text and uncomment the tags hint, like a user would
+    let editor = fake_editor(
+        &home,
+        r#"{ printf 'Wrote this in my editor\n\nSecond paragraph\n'; sed 's/^# tags: .*/tags: editor, cli/' "$1"; } > "$1.new" && mv "$1.new" "$1""#,
+    );
+
+    let output = stdout_of(cli(&home).env("VISUAL", &editor).args(["journal", "add"]));
+    assert!(output.contains("created successfully with ID: 1"));
+    let show = stdout_of(cli(&home).args(["journal", "show", "1"]));
+    assert!(show.contains("---\nWrote this in my editor\n\nSecond paragraph\n---"));
+    assert!(show.contains("Tags: cli, editor"));
+
+    // Editing keeps the existing text and tags in the template
+    let editor = fake_editor(&home, r#"sed -i 's/Second paragraph/Edited paragraph/' "$1""#);
+    stdout_of(cli(&home).env("VISUAL", &editor).args(["journal", "edit", "1"]));
+    let show = stdout_of(cli(&home).args(["journal", "show", "1"]));
+    assert!(show.contains("Edited paragraph"));
+    assert!(show.contains("Tags: cli, editor"));
+
+    // Leaving the template untouched aborts
+    let editor = fake_editor(&home, "true");
+    cli(&home).env("VISUAL", &editor).args(["journal", "add"]).assert().failure();
+}
+
+#[test]
+fn test_snippet_list_search_show_and_rm() {
+    let home = TempDir::new().unwrap();
+    stdout_of(cli(&home).args(["snippet", "add", "--code", "let email_re = 1;", "--lang", "rust"]));
+    stdout_of(cli(&home).args(["snippet", "add", "--code", "console.log(1);", "--lang", "js"]));
+
+    let list = stdout_of(cli(&home).args(["snippet", "list", "--lang", "js"]));
+    assert!(list.contains("#2"));
+    assert!(list.contains("console.log(1);"));
+    assert!(!list.contains("#1"));
+
+    let search = stdout_of(cli(&home).args(["snippet", "search", "EMAIL"]));
+    assert!(search.contains("#1"));
+    assert!(!search.contains("#2"));
+
+    let show = stdout_of(cli(&home).args(["snippet", "show", "1"]));
+    assert!(show.contains("Language: rust"));
+
+    stdout_of(cli(&home).args(["snippet", "rm", "1", "--yes"]));
+    let show = stdout_of(cli(&home).args(["snippet", "show", "1"]));
+    assert!(show.contains("not found"));
+}
+
+#[test]
+fn test_lang_commands_and_aliases() {
+    let home = TempDir::new().unwrap();
+    let added = stdout_of(cli(&home).args(["snippet", "add", "--code", "print(1)", "--lang", "py"]));
+    assert!(added.contains("ID: 1"));
+
+    stdout_of(cli(&home).args(["lang", "add", "kotlin"]));
+    stdout_of(cli(&home).args(["lang", "alias", "kt", "kotlin"]));
+    stdout_of(cli(&home).args(["snippet", "add", "--code", "fun main() {}", "--lang", "kt"]));
+
+    let list = stdout_of(cli(&home).args(["lang", "list"]));
+    let kotlin = list.lines().find(|line| line.starts_with("kotlin")).unwrap();
+    assert!(kotlin.contains('1') && kotlin.contains("kt"));
+    assert!(list.lines().any(|line| line.starts_with("python") && line.contains("py")));
+
+    let output = cli(&home).args(["lang", "rm", "kotlin"]).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("used by 1 snippet"));
+
+    stdout_of(cli(&home).args(["lang", "alias", "kt", "--remove"]));
+    let list = stdout_of(cli(&home).args(["lang", "list"]));
+    assert!(!list.contains("kt"));
+}
+
+#[test]
+fn test_snippet_add_detects_language() {
+    let home = TempDir::new().unwrap();
+    let file = home.path().join("cleanup.sh");
+    std::fs::write(&file, "rm -rf build/\n").unwrap();
+
+    let added = stdout_of(cli(&home).args(["snippet", "add", "--file"]).arg(&file));
+    assert!(added.contains("Detected bash from the file extension"));
+    let show = stdout_of(cli(&home).args(["snippet", "show", "1"]));
+    assert!(show.contains("Language: bash"));
+    assert!(show.contains("rm -rf build/"));
+    // Piped output is never colored
+    assert!(!show.contains('\x1b'));
+
+    let output = cli(&home).args(["snippet", "show", "1", "--theme", "nope"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("base16-ocean.dark"));
+
+    // Unsure guesses are refused when nobody can confirm them
+    let output = cli(&home).args(["snippet", "add", "--code", "print(x)"]).write_stdin("").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--lang"));
+}
+
+#[test]
+fn test_snippet_add_with_metadata() {
+    let home = TempDir::new().unwrap();
+    stdout_of(cli(&home).args([
+        "snippet", "add", "--code", "/^\\S+@\\S+$/", "--lang", "js",
+        "--title", "Email regex", "--desc", "Loose email check",
+        "--source", "https://stackoverflow.com/q/46155", "--tags", "regex,email",
+    ]));
+
+    let show = stdout_of(cli(&home).args(["snippet", "show", "1"]));
+    assert!(show.contains("Title: Email regex"));
+    assert!(show.contains("Tags: regex, email"));
+    assert!(show.contains("Source: https://stackoverflow.com/q/46155"));
+    assert!(show.contains("Loose email check"));
+
+    let list = stdout_of(cli(&home).args(["snippet", "list"]));
+    assert!(list.contains("Email regex"));
+    let search = stdout_of(cli(&home).args(["snippet", "search", "stackoverflow"]));
+    assert!(search.contains("#1"));
+}
+
+#[test]
+fn test_snippet_clipboard_round_trip() {
+    let home = TempDir::new().unwrap();
+    let clipboard = home.path().join("clipboard.txt");
+    std::fs::write(&clipboard, "docker system prune -af\n").unwrap();
+    let copy = stub_script(&home, "copy.sh", &format!("cat > '{}'", clipboard.display()));
+    let paste = stub_script(&home, "paste.sh", &format!("cat '{}'", clipboard.display()));
+    let with_clipboard = |cmd: &mut Command| {
+        cmd.env("CLINOTES_CLIPBOARD_COPY", &copy).env("CLINOTES_CLIPBOARD_PASTE", &paste);
+    };
+
+    let mut add = cli(&home);
+    with_clipboard(&mut add);
+    let added = stdout_of(add.args(["snippet", "add", "--from-clipboard", "--title", "Free disk space"]));
+    assert!(added.contains("Detected bash"));
+
+    std::fs::write(&clipboard, "").unwrap();
+    let mut copy_cmd = cli(&home);
+    with_clipboard(&mut copy_cmd);
+    let copied = stdout_of(copy_cmd.args(["snippet", "copy", "1"]));
+    assert!(copied.contains("Copied snippet #1"));
+    assert_eq!(std::fs::read_to_string(&clipboard).unwrap(), "docker system prune -af\n");
+
+    let mut missing = cli(&home);
+    with_clipboard(&mut missing);
+    assert!(!missing.args(["snippet", "copy", "99"]).output().unwrap().status.success());
+}
+
+#[test]
+fn test_snippet_render_fills_placeholders() {
+    let home = TempDir::new().unwrap();
+    stdout_of(cli(&home).args([
+        "snippet", "add", "--lang", "bash", "--code", "docker run -p {{port:8080}}:80 {{image}}",
+    ]));
+
+    let show = stdout_of(cli(&home).args(["snippet", "show", "1"]));
+    assert!(show.contains("Parameters: port (default: 8080), image"));
+
+    let rendered = stdout_of(cli(&home).args(["snippet", "render", "1", "--set", "image=nginx"]));
+    assert_eq!(rendered, "docker run -p 8080:80 nginx\n");
+    let rendered = stdout_of(cli(&home).args(["snippet", "render", "1", "--set", "image=redis", "--set", "port=6379"]));
+    assert_eq!(rendered, "docker run -p 6379:80 redis\n");
+
+    // Without a terminal to ask on, missing values are an error
+    let output = cli(&home).args(["snippet", "render", "1"]).write_stdin("").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Missing values for: image"));
+
+    let output = cli(&home).args(["snippet", "render", "1", "--set", "tag=x"]).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no parameter 'tag'"));
+
+    // `snippet run` fills in the placeholders the same way, then executes the command
+    stdout_of(cli(&home).args(["snippet", "add", "--lang", "bash", "--code", "echo {{greeting:hello}} {{who}}"]));
+    let ran = stdout_of(cli(&home).args(["snippet", "run", "2", "--yes", "--set", "who=world"]));
+    assert!(ran.contains("echo hello world\n---"));
+    assert!(ran.ends_with("hello world\n"));
+    let output = cli(&home).args(["snippet", "run", "2", "--yes"]).write_stdin("").output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Missing values for: who"));
+    let show = stdout_of(cli(&home).args(["snippet", "show", "2"]));
+    assert!(show.contains("Runs: 1 ("));
+}
+
+#[test]
+fn test_snippet_exec_runs_shell_snippets() {
+    let home = TempDir::new().unwrap();
+    stdout_of(cli(&home).args(["snippet", "add", "--lang", "sh", "--code", "echo hello {{who:world}}"]));
+    stdout_of(cli(&home).args(["snippet", "add", "--lang", "bash", "--code", "exit 3"]));
+    stdout_of(cli(&home).args(["snippet", "add", "--lang", "python", "--code", "print('hi')"]));
+
+    let ran = stdout_of(cli(&home).args(["snippet", "exec", "1", "--yes", "--set", "who=runbook"]));
+    assert!(ran.contains("echo hello runbook\n---"));
+    assert!(ran.contains("hello runbook\n"));
+
+    // Declining the confirmation runs nothing
+    let output = cli(&home).args(["snippet", "exec", "1"]).write_stdin("n\n").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Aborted."));
+
+    let output = cli(&home).args(["snippet", "exec", "2", "--yes"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = cli(&home).args(["snippet", "exec", "3", "--yes"]).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("only shell snippets"));
+
+    let show = stdout_of(cli(&home).args(["snippet", "show", "1"]));
+    assert!(show.contains("Runs: 1 ("));
+    let show = stdout_of(cli(&home).args(["snippet", "show", "2"]));
+    assert!(show.contains("exit 3"));
+}
+
+#[test]
+fn test_snippet_backlinks() {
+    let home = TempDir::new().unwrap();
+    stdout_of(cli(&home).args(["snippet", "add", "--lang", "bash", "--code", "git bisect start", "--title", "Bisect"]));
+    let added = stdout_of(cli(&home).args(["journal", "add", "Found the regression with bisect", "--snippet", "1"]));
+    assert!(added.contains("Linked snippet #1"));
+    let note = home.path().join("git.md");
+    std::fs::write(&note, "# Git\n").unwrap();
+    stdout_of(cli(&home).args(["note", "add", note.to_str().unwrap()]));
+    stdout_of(cli(&home).args(["snippet", "link", "1", "--note", "1", "--desc", "reference"]));
+
+    let show = stdout_of(cli(&home).args(["snippet", "show", "1"]));
+    assert!(show.contains("Used in:"));
+    assert!(show.contains("journal entry #1"));
+    assert!(show.contains("Found the regression with bisect"));
+    assert!(show.contains("learning note #1 git.md — reference"));
+
+    let journal = stdout_of(cli(&home).args(["journal", "show", "1"]));
+    assert!(journal.contains("#1 Bisect"));
+
+    stdout_of(cli(&home).args(["snippet", "unlink", "1", "--journal", "1"]));
+    let show = stdout_of(cli(&home).args(["snippet", "show", "1"]));
+    assert!(!show.contains("journal entry #1"));
+
+    // Linking a missing snippet fails before the entry is created
+    let output = cli(&home).args(["journal", "add", "Nope", "--snippet", "42"]).output().unwrap();
+    assert!(!output.status.success());
+    let list = stdout_of(cli(&home).args(["journal", "list"]));
+    assert!(!list.contains("Nope"));
+}
+
+#[test]
+fn test_db_check_and_repair() {
+    let home = TempDir::new().unwrap();
+    stdout_of(cli(&home).args(["db", "migrate"]));
+    assert!(stdout_of(cli(&home).args(["db", "check"])).contains("No orphaned rows found"));
+
+    let db_path = home.path().join("clinotes").join("clinotes.db");
+    let db = cli_notes::db::Database::new(db_path.to_str().unwrap()).unwrap();
+    db.conn()
+        .execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             INSERT INTO snippets_used (snippet_id, journal_entry_id) VALUES (42, NULL);",
+        )
+        .unwrap();
+    drop(db);
+
+    let output = cli(&home).args(["db", "check"]).output().unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("snippets_used row 1 references a missing code_snippets row"));
+
+    let repair = stdout_of(cli(&home).args(["db", "check", "--repair"]));
+    assert!(repair.contains("→ deleted"));
+    assert!(stdout_of(cli(&home).args(["db", "check"])).contains("No orphaned rows found"));
+}
+
+#[test]
+fn test_note_add_show_and_search() {
+    let home = TempDir::new().unwrap();
+    let notes = TempDir::new().unwrap();
+    let path = notes.path().join("docker.md");
+    std::fs::write(&path, "# Docker tips\n\n## Cleanup\n\nPrune dangling images with `docker image prune`.\n").unwrap();
+
+    let add = stdout_of(cli(&home).args(["note", "add", path.to_str().unwrap()]));
+    assert!(add.contains("Note 'Docker tips' linked successfully with ID: 1"));
+
+    let show = stdout_of(cli(&home).args(["note", "show", "1"]));
+    assert!(show.contains("Title: Docker tips"));
+    assert!(show.contains("Words: 10"));
+    assert!(show.contains("Outline:\n  Docker tips\n    Cleanup"));
+
+    let search = stdout_of(cli(&home).args(["note", "search", "dangling"]));
+    assert!(search.contains("#1 Docker tips"));
+    assert!(search.contains("**dangling**"));
+
+    cli(&home).args(["note", "add", "/does/not/exist.md"]).assert().failure();
+}
+
+#[test]
+fn test_note_scan() {
+    let home = TempDir::new().unwrap();
+    let notes = TempDir::new().unwrap();
+    let write = |path: &str, content: &str| {
+        let path = notes.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    write("rust.md", "# Rust\n");
+    write("git/rebase.md", "# Rebase\n");
+    write("git/rebase-copy.md", "# Rebase\n");
+    write("target/generated.md", "# Generated\n");
+    write("vendor/lib.md", "# Vendored\n");
+    write("todo.txt", "not markdown");
+    write(".gitignore", "target/\n");
+    let dir = notes.path().to_str().unwrap();
+
+    let scan = stdout_of(cli(&home).args(["note", "scan", dir, "--exclude", "vendor"]));
+    assert!(scan.contains("+ rust.md"));
+    assert!(scan.contains("+ git/rebase-copy.md"));
+    assert!(scan.contains("= git/rebase.md (same content as #1)"));
+    assert!(!scan.contains("generated.md"));
+    assert!(!scan.contains("lib.md"));
+    assert!(scan.contains("Scanned 3 file(s): 2 added, 0 changed, 1 skipped"));
+
+    write("rust.md", "# Rust\n\nOwnership.\n");
+    let rescan = stdout_of(cli(&home).args(["note", "scan", dir, "--exclude", "vendor"]));
+    assert!(rescan.contains("~ rust.md"));
+    assert!(rescan.contains("Scanned 3 file(s): 0 added, 1 changed, 2 skipped"));
+
+    let txt = stdout_of(cli(&home).args(["note", "scan", dir, "--glob", "*.txt"]));
+    assert!(txt.contains("+ todo.txt"));
+}
+
+#[test]
+fn test_note_sync_and_list() {
+    let home = TempDir::new().unwrap();
+    let notes = TempDir::new().unwrap();
+    let root = notes.path();
+    std::fs::write(root.join("a.md"), "# Alpha\n").unwrap();
+    std::fs::write(root.join("b.md"), "# Beta\n").unwrap();
+    stdout_of(cli(&home).args(["note", "scan", root.to_str().unwrap()]));
+
+    std::fs::write(root.join("a.md"), "# Alpha two\n").unwrap();
+    std::fs::remove_file(root.join("b.md")).unwrap();
+    let stale = stdout_of(cli(&home).args(["note", "list", "--stale"]));
+    assert!(stale.contains("#1 Alpha"));
+    assert!(!stale.contains("Beta"));
+
+    let sync = stdout_of(cli(&home).args(["note", "sync"]));
+    assert!(sync.contains("b.md (#2) is missing"));
+    assert!(sync.contains("Synced 2 note(s): 1 updated, 0 moved, 1 missing"));
+
+    let missing = stdout_of(cli(&home).args(["note", "list", "--missing"]));
+    assert!(missing.contains("#2 Beta"));
+    assert!(missing.contains("missing since"));
+    assert!(!missing.contains("Alpha"));
+    let all = stdout_of(cli(&home).args(["note", "list"]));
+    assert!(all.contains("#1 Alpha two"));
+
+    // Moving the file back under the scanned directory relinks it
+    std::fs::create_dir(root.join("old")).unwrap();
+    std::fs::write(root.join("old/b.md"), "# Beta\n").unwrap();
+    let sync = stdout_of(cli(&home).args(["note", "sync"]));
+    assert!(sync.contains("moved to"));
+    assert!(stdout_of(cli(&home).args(["note", "list", "--missing"])).contains("No notes found"));
+}
+
+#[test]
+fn test_watch() {
+    use std::io::{BufRead, BufReader};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let home = TempDir::new().unwrap();
+    let output = cli(&home).arg("watch").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("No directories to watch"));
+
+    let notes = TempDir::new().unwrap();
+    std::fs::write(notes.path().join("old.md"), "# Old\n").unwrap();
+    stdout_of(cli(&home).args(["note", "scan", notes.path().to_str().unwrap()]));
+    // Changed while not watching, picked up on start
+    std::fs::write(notes.path().join("old.md"), "# Old, edited\n").unwrap();
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("cli_notes"))
+        .args(["watch", "--debounce-ms", "100"])
+        .env("XDG_CONFIG_HOME", home.path())
+        .env("HOME", home.path())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let (lines, received) = mpsc::channel();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    std::thread::spawn(move || {
+        for line in stdout.lines() {
+            let _ = lines.send(line.unwrap());
+        }
+    });
+    let wait_for = |text: &str| -> String {
+        loop {
+            match received.recv_timeout(Duration::from_secs(10)) {
+                Ok(line) if line.contains(text) => return line,
+                Ok(_) => continue,
+                Err(_) => panic!("'{}' was not printed", text),
+            }
+        }
+    };
+
+    assert!(wait_for("~ ").contains("old.md (#1)"));
+    wait_for("Watching 1 director(ies)");
+    std::fs::write(notes.path().join("new.md"), "# New\n").unwrap();
+    let added = wait_for("+ ");
+    assert!(added.starts_with('['));
+    assert!(added.contains("new.md (#2)"));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    let show = stdout_of(cli(&home).args(["note", "show", "1"]));
+    assert!(show.contains("Title: Old, edited"));
+}
+
+#[test]
+fn test_note_links_and_graph() {
+    let home = TempDir::new().unwrap();
+    let notes = TempDir::new().unwrap();
+    std::fs::write(notes.path().join("a.md"), "# Alpha\n\nSee [[b]] and [[Gamma]].\n").unwrap();
+    std::fs::write(notes.path().join("b.md"), "# Beta \"quoted\"\n\nBack to [alpha](a.md).\n").unwrap();
+    stdout_of(cli(&home).args(["note", "scan", notes.path().to_str().unwrap()]));
+
+    let show = stdout_of(cli(&home).args(["note", "show", "1"]));
+    assert!(show.contains("Links:\n  → #2 Beta \"quoted\" ([[b]])"));
+    assert!(show.contains("Broken links:\n  ✗ [[Gamma]]"));
+    assert!(show.contains("Backlinks:\n  ← #2 Beta"));
+
+    let graph = stdout_of(cli(&home).args(["note", "graph", "--format", "dot"]));
+    assert!(graph.starts_with("digraph notes {\n"));
+    assert!(graph.contains("  n1 [label=\"Alpha\"];"));
+    assert!(graph.contains("  n2 [label=\"Beta \\\"quoted\\\"\"];"));
+    assert!(graph.contains("  n1 -> n2;"));
+    assert!(graph.contains("  n2 -> n1;"));
+    assert!(graph.contains("  n1 -> \"broken:[[Gamma]]\" [style=dashed];"));
+    assert!(graph.ends_with("}\n"));
+
+    cli(&home).args(["note", "graph", "--format", "svg"]).assert().failure();
+}
@@ -1,8 +1,11 @@
 // yugonline-clinotes/tests/dao_tests.rs
 
+use chrono::TimeZone;
 use cli_notes::dao;
+use cli_notes::date_range::DateRange;
 use cli_notes::db;
-use cli_notes::models::{CodeSnippet, JournalEntry};
+use cli_notes::models::{CodeSnippet, JournalEntry, LinkTarget, NoteImport, NoteLinkKind, NoteSync, TagSource};
+use proptest::prelude::*;
 
 /// Creates an in-memory SQLite database and returns an initialized `Database` instance.
 fn setup_test_db() -> db::Database {
@@ -29,35 +32,32 @@ fn test_create_and_read_code_snippet() {
 
     // Create a code snippet
     let snippet = CodeSnippet {
-        id: 0,
-        full_code: String::from("fn main() { println!(\"Hello, world!\"); }"),
-        created_at: chrono::Local::now(),
-        updated_at: chrono::Local::now(),
         language_id: rust_lang_id, // Use the dynamically fetched ID
+        ..CodeSnippet::new(String::from("fn main() { println!(\"Hello, world!\"); }"))
     };
 
     // Create the code snippet in the database
-    let snippet_id = dao::create_code_snippet(db.conn(), &snippet, "rust").unwrap();
+    let snippet_id = dao::create_code_snippet(db.conn(), &snippet, Some("rust"), None).unwrap();
 
     // Read the code snippet back from the database
     let mut read_snippet = dao::read_code_snippet(db.conn(), snippet_id).unwrap().unwrap();
 
-    // Verify that the code snippet was created correctly - account for preprocessed code
-    assert!(read_snippet.full_code.starts_with("'''\n"));
-    assert!(read_snippet.full_code.ends_with("\n'''"));
-    assert!(read_snippet.full_code.contains("fn main() { println!(\"Hello, world!\"); }"));
+    // Snippets are stored verbatim
+    assert_eq!(read_snippet.full_code, snippet.full_code);
 
     // Verify language_id
     assert_eq!(read_snippet.language_id, rust_lang_id);
 
     // Update the code snippet
     read_snippet.full_code.push_str("\n// Updated code");
-    // NOTE: This assumes the `update_code_snippet` bug I mentioned before is also fixed.
     dao::update_code_snippet(db.conn(), &read_snippet, "rust").unwrap();
 
     // Read the updated code snippet
     let updated_snippet = dao::read_code_snippet(db.conn(), snippet_id).unwrap().unwrap();
-    assert!(updated_snippet.full_code.ends_with("\n// Updated code\n'''"));
+    assert_eq!(
+        updated_snippet.full_code,
+        "fn main() { println!(\"Hello, world!\"); }\n// Updated code"
+    );
 
     // Delete the code snippet
     dao::delete_code_snippet(db.conn(), snippet_id).unwrap();
@@ -131,4 +131,870 @@ fn test_language_functions() {
     // Test language_exists with non-existent language
     let exists_not = dao::language_exists(db.conn(), "nonexistent").unwrap();
     assert!(!exists_not);
-}
\ No newline at end of file
+}
+fn add_entry(db: &db::Database, text: &str, tags: Option<&str>) -> i64 {
+    let journal_entry = JournalEntry::new(String::from(text), tags.map(String::from));
+    dao::create_journal_entry(db.conn(), &journal_entry).unwrap()
+}
+
+fn search_ids(db: &db::Database, query: &str) -> Vec<i64> {
+    dao::search_journal_entries(db.conn(), query)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.id)
+        .collect()
+}
+
+#[test]
+fn test_full_text_search_journal_entries() {
+    let db = setup_test_db();
+    let borrow = add_entry(&db, "Spent the morning borrowing trouble with the borrow checker", None);
+    let async_rust = add_entry(&db, "Rust async runtimes are confusing", Some("rust"));
+    let plain_rust = add_entry(&db, "Rust pattern matching is lovely", None);
+    let phrase = add_entry(&db, "Pairing session: pattern matching in Elixir", None);
+
+    // Prefix queries
+    let mut ids = search_ids(&db, "borr*");
+    ids.sort();
+    assert_eq!(ids, vec![borrow]);
+
+    // Boolean operators
+    assert_eq!(search_ids(&db, "rust NOT async"), vec![plain_rust]);
+
+    // Exact phrases
+    let mut ids = search_ids(&db, "\"pattern matching\"");
+    ids.sort();
+    assert_eq!(ids, vec![plain_rust, phrase]);
+
+    // Tags are indexed too
+    assert!(search_ids(&db, "rust").contains(&async_rust));
+
+    // Plain questions are not valid FTS syntax and fall back to matching any word
+    assert!(search_ids(&db, "How do I feel about async?").contains(&async_rust));
+    assert!(search_ids(&db, "???").is_empty());
+}
+
+#[test]
+fn test_full_text_search_ranking_and_snippets() {
+    let db = setup_test_db();
+    add_entry(&db, "Today was about many things, one of them sqlite", None);
+    let best = add_entry(&db, "sqlite sqlite sqlite: indexes, sqlite pragmas", None);
+
+    let results = dao::search_journal_entries_ranked(db.conn(), "sqlite", Some(1), ("<", ">")).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].entry.id, best);
+    assert!(results[0].snippet.contains("<sqlite>"));
+}
+
+#[test]
+fn test_full_text_index_follows_updates_and_deletes() {
+    let db = setup_test_db();
+    let id = add_entry(&db, "Learning about tokio", None);
+
+    db.conn()
+        .execute("UPDATE journal_entries SET entry = 'Learning about rayon' WHERE id = ?1", [id])
+        .unwrap();
+    assert!(search_ids(&db, "tokio").is_empty());
+    assert_eq!(search_ids(&db, "rayon"), vec![id]);
+
+    db.conn().execute("DELETE FROM journal_entries WHERE id = ?1", [id]).unwrap();
+    assert!(search_ids(&db, "rayon").is_empty());
+}
+
+#[test]
+fn test_journal_entry_tags_are_normalized() {
+    let db = setup_test_db();
+    let id = add_entry(&db, "Worked on my Rust project", Some(" Rust, CLI ,rust,"));
+
+    let entry = dao::read_journal_entry(db.conn(), id).unwrap().unwrap();
+    let user_tags: Vec<&str> = entry
+        .tag_list
+        .iter()
+        .filter(|tag| tag.source == TagSource::User)
+        .map(|tag| tag.name.as_str())
+        .collect();
+    let ai_tags: Vec<&str> = entry
+        .tag_list
+        .iter()
+        .filter(|tag| tag.source == TagSource::Ai)
+        .map(|tag| tag.name.as_str())
+        .collect();
+    assert_eq!(user_tags, vec!["cli", "rust"]);
+    assert_eq!(ai_tags, vec!["project", "rust", "work"]);
+}
+
+#[test]
+fn test_filter_entries_by_exact_tag() {
+    let db = setup_test_db();
+    let rust = add_entry(&db, "Plain entry", Some("rust"));
+    add_entry(&db, "Another plain entry", Some("rustacean"));
+
+    let entries = dao::get_journal_entries_by_tag(db.conn(), "Rust").unwrap();
+    assert_eq!(entries.iter().map(|e| e.id).collect::<Vec<_>>(), vec![rust]);
+}
+
+#[test]
+fn test_rename_merge_and_count_tags() {
+    let db = setup_test_db();
+    let first = add_entry(&db, "Plain entry", Some("js, frontend"));
+    let second = add_entry(&db, "Plain entry", Some("javascript"));
+    add_entry(&db, "Plain entry", Some("frontend"));
+
+    let counts = dao::count_tags(db.conn(), Some(TagSource::User)).unwrap();
+    assert_eq!(counts[0], ("frontend".to_string(), 2));
+
+    // Renaming onto an existing tag is refused
+    assert!(dao::rename_tag(db.conn(), "js", "javascript").is_err());
+    assert!(dao::rename_tag(db.conn(), "missing", "anything").is_err());
+
+    dao::merge_tags(db.conn(), &["js"], "javascript").unwrap();
+    dao::rename_tag(db.conn(), "javascript", "ecmascript").unwrap();
+
+    let counts = dao::count_tags(db.conn(), Some(TagSource::User)).unwrap();
+    assert_eq!(
+        counts,
+        vec![("ecmascript".to_string(), 2), ("frontend".to_string(), 2)]
+    );
+
+    // The denormalized tag strings follow the normalized tags
+    let entry = dao::read_journal_entry(db.conn(), first).unwrap().unwrap();
+    assert_eq!(entry.tags.as_deref(), Some("ecmascript, frontend"));
+    let entry = dao::read_journal_entry(db.conn(), second).unwrap().unwrap();
+    assert_eq!(entry.tags.as_deref(), Some("ecmascript"));
+
+    let usage = dao::list_tags(db.conn()).unwrap();
+    assert!(usage.iter().all(|tag| tag.name != "js" && tag.name != "javascript"));
+}
+
+#[test]
+fn test_summaries_rank_topics_by_frequency() {
+    let db = setup_test_db();
+    add_entry(&db, "Learning Rust", Some("cli"));
+    add_entry(&db, "More Rust today", Some("rust, cli"));
+    add_entry(&db, "Rust again, then some work", None);
+    add_entry(&db, "Nothing in particular", Some("cli"));
+
+    let expected = vec![
+        ("cli".to_string(), 3),
+        ("rust".to_string(), 3),
+        ("learning".to_string(), 1),
+        ("work".to_string(), 1),
+    ];
+
+    let entries = dao::get_journal_entries_in_range(db.conn(), &DateRange::all()).unwrap();
+    let summary = dao::summarize_journal_entries(&entries);
+    assert_eq!(summary.total_entries, 4);
+    assert_eq!(summary.common_topics, expected);
+
+    let summary = dao::generate_summary_for_range(db.conn(), &DateRange::all()).unwrap();
+    assert_eq!(summary.total_entries, 4);
+    assert_eq!(summary.common_topics, expected);
+}
+
+#[test]
+fn test_journal_entries_in_date_range() {
+    let db = setup_test_db();
+    let old = add_entry(&db, "An old entry", None);
+    let recent = add_entry(&db, "A recent entry", None);
+    // Dates are stored in UTC, so they are set from local times to not depend on the timezone
+    let now = chrono::Local.with_ymd_and_hms(2026, 5, 13, 15, 30, 0).unwrap();
+    let set_date = |id: i64, date: chrono::DateTime<chrono::Local>| {
+        let date = date.with_timezone(&chrono::Utc).format("%Y-%m-%d %H:%M:%S").to_string();
+        db.conn()
+            .execute("UPDATE journal_entries SET date = ?1 WHERE id = ?2", rusqlite::params![date, id])
+            .unwrap();
+    };
+    set_date(old, chrono::Local.with_ymd_and_hms(2025, 12, 31, 12, 0, 0).unwrap());
+    set_date(recent, now - chrono::Duration::hours(1));
+
+    let ids = |range: &DateRange| -> Vec<i64> {
+        dao::get_journal_entries_in_range(db.conn(), range)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.id)
+            .collect()
+    };
+
+    assert_eq!(ids(&DateRange::all()), vec![recent, old]);
+    assert_eq!(ids(&DateRange::parse_period("today", now).unwrap()), vec![recent]);
+    assert_eq!(ids(&DateRange::from_bounds(None, Some("2026-01-01"), now).unwrap()), vec![old]);
+    assert_eq!(ids(&DateRange::from_bounds(Some("2025-12-01"), Some("2025-12-31"), now).unwrap()), vec![old]);
+
+    let summary = dao::generate_summary_for_range(db.conn(), &DateRange::from_bounds(Some("3d"), None, now).unwrap()).unwrap();
+    assert_eq!(summary.total_entries, 1);
+}
+
+#[test]
+fn test_update_journal_entry_reruns_analysis() {
+    let db = setup_test_db();
+    let id = add_entry(&db, "Great day writing Rust", Some("rust"));
+
+    let mut entry = dao::read_journal_entry(db.conn(), id).unwrap().unwrap();
+    entry.entry = String::from("Terrible day, chasing a bug at work");
+    entry.tags = Some(String::from("oncall"));
+    dao::update_journal_entry(db.conn(), &entry).unwrap();
+
+    let updated = dao::read_journal_entry(db.conn(), id).unwrap().unwrap();
+    assert_eq!(updated.sentiment.as_deref(), Some("negative"));
+    assert_eq!(updated.ai_tags.as_deref(), Some("debugging,work"));
+    let names: Vec<&str> = updated.tag_list.iter().map(|tag| tag.name.as_str()).collect();
+    assert_eq!(names, vec!["oncall", "debugging", "work"]);
+    assert!(dao::get_journal_entries_by_tag(db.conn(), "rust").unwrap().is_empty());
+
+    entry.id = 999;
+    assert!(matches!(dao::update_journal_entry(db.conn(), &entry), Err(dao::DaoError::NotFound(_))));
+}
+
+#[test]
+fn test_delete_journal_entry_removes_links() {
+    let db = setup_test_db();
+    let id = add_entry(&db, "Used a snippet today", Some("rust"));
+    let snippet = CodeSnippet::new(String::from("fn main() {}"));
+    let snippet_id = dao::create_code_snippet(db.conn(), &snippet, Some("rust"), None).unwrap();
+    dao::link_snippet(db.conn(), snippet_id, LinkTarget::Journal(id), Some("used")).unwrap();
+
+    dao::delete_journal_entry(db.conn(), id).unwrap();
+
+    assert!(dao::read_journal_entry(db.conn(), id).unwrap().is_none());
+    let count = |table: &str| -> i64 {
+        db.conn()
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    };
+    assert_eq!(count("snippets_used"), 0);
+    assert_eq!(count("entry_tags"), 0);
+    assert!(dao::read_code_snippet(db.conn(), snippet_id).unwrap().is_some());
+
+    assert!(matches!(dao::delete_journal_entry(db.conn(), id), Err(dao::DaoError::NotFound(_))));
+}
+
+#[test]
+fn test_list_journal_entries_pagination() {
+    let db = setup_test_db();
+    let ids: Vec<i64> = (0..5)
+        .map(|i| add_entry(&db, &format!("Entry {}", i), Some(if i % 2 == 0 { "even" } else { "odd" })))
+        .collect();
+
+    let all = DateRange::all();
+    assert_eq!(dao::count_journal_entries(db.conn(), &all, None).unwrap(), 5);
+    assert_eq!(dao::count_journal_entries(db.conn(), &all, Some("even")).unwrap(), 3);
+
+    let page = |limit, offset| -> Vec<i64> {
+        dao::list_journal_entries(db.conn(), &all, None, limit, offset)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.id)
+            .collect()
+    };
+    assert_eq!(page(2, 0), vec![ids[4], ids[3]]);
+    assert_eq!(page(2, 2), vec![ids[2], ids[1]]);
+    assert_eq!(page(2, 4), vec![ids[0]]);
+
+    let odd: Vec<i64> = dao::list_journal_entries(db.conn(), &all, Some("odd"), 10, 0)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.id)
+        .collect();
+    assert_eq!(odd, vec![ids[3], ids[1]]);
+}
+
+fn add_note(db: &db::Database, dir: &tempfile::TempDir, name: &str, content: &str) -> i64 {
+    let path = dir.path().join(name);
+    std::fs::write(&path, content).unwrap();
+    dao::create_learning_note(db.conn(), path.to_str().unwrap()).unwrap()
+}
+
+fn add_snippet(db: &db::Database, code: &str, lang: &str) -> i64 {
+    let snippet = CodeSnippet::new(String::from(code));
+    dao::create_code_snippet(db.conn(), &snippet, Some(lang), None).unwrap()
+}
+
+#[test]
+fn test_list_and_search_code_snippets() {
+    let db = setup_test_db();
+    let first = add_snippet(&db, "fn parse_email() {}", "rust");
+    let second = add_snippet(&db, "const EMAIL = /.+@.+/;", "js");
+    let third = add_snippet(&db, "fn main() {}", "rust");
+
+    let ids = |snippets: Vec<CodeSnippet>| snippets.into_iter().map(|s| s.id).collect::<Vec<_>>();
+    assert_eq!(ids(dao::list_code_snippets(db.conn(), None, 10).unwrap()), vec![third, second, first]);
+    assert_eq!(ids(dao::list_code_snippets(db.conn(), None, 2).unwrap()), vec![third, second]);
+    assert_eq!(ids(dao::list_code_snippets(db.conn(), Some("rust"), 10).unwrap()), vec![third, first]);
+
+    assert_eq!(ids(dao::search_code_snippets(db.conn(), "email").unwrap()), vec![second, first]);
+    assert_eq!(ids(dao::search_code_snippets(db.conn(), "%").unwrap()), Vec::<i64>::new());
+
+    dao::delete_code_snippet(db.conn(), first).unwrap();
+    assert!(matches!(dao::delete_code_snippet(db.conn(), first), Err(dao::DaoError::NotFound(_))));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn prop_snippet_round_trips_verbatim(
+        code in any::<String>(),
+        lang in prop::sample::select(vec!["rust", "js", "python", "c++"]),
+    ) {
+        let db = setup_test_db();
+        let id = add_snippet(&db, &code, lang);
+
+        let read = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+        prop_assert_eq!(&read.full_code, &code);
+
+        // Saving an unchanged snippet must not alter it
+        dao::update_code_snippet(db.conn(), &read, lang).unwrap();
+        let reread = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+        prop_assert_eq!(&reread.full_code, &code);
+        prop_assert_eq!(reread.language_id, read.language_id);
+    }
+}
+
+#[test]
+fn test_every_seeded_language_accepts_snippets() {
+    let db = setup_test_db();
+    for (language, _) in dao::list_languages(db.conn()).unwrap() {
+        let id = add_snippet(&db, "print('hi')", &language.name);
+        let snippet = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+        assert_eq!(snippet.language_id, language.id);
+    }
+}
+
+#[test]
+fn test_language_aliases() {
+    let db = setup_test_db();
+    let python = dao::get_language_id_from_name(db.conn(), "python").unwrap();
+    assert_eq!(dao::get_language_id_from_name(db.conn(), "PY").unwrap(), python);
+    assert_eq!(
+        dao::get_language_id_from_name(db.conn(), "javascript").unwrap(),
+        dao::get_language_id_from_name(db.conn(), "js").unwrap()
+    );
+
+    let id = add_snippet(&db, "print(1)", "py");
+    let listed = dao::list_code_snippets(db.conn(), Some("python"), 10).unwrap();
+    assert_eq!(listed.iter().map(|s| s.id).collect::<Vec<_>>(), vec![id]);
+
+    dao::create_language(db.conn(), "Kotlin").unwrap();
+    dao::add_language_alias(db.conn(), "kt", "kotlin").unwrap();
+    assert!(matches!(dao::add_language_alias(db.conn(), "py", "kotlin"), Err(dao::DaoError::InvalidInput(_))));
+    assert!(matches!(dao::create_language(db.conn(), "kt"), Err(dao::DaoError::InvalidInput(_))));
+    assert!(matches!(dao::create_language(db.conn(), "two words"), Err(dao::DaoError::InvalidInput(_))));
+
+    let languages = dao::list_languages(db.conn()).unwrap();
+    let (kotlin, snippets) = languages.iter().find(|(l, _)| l.name == "kotlin").unwrap();
+    assert_eq!(kotlin.aliases, vec!["kt"]);
+    assert_eq!(*snippets, 0);
+
+    // Languages in use are kept
+    assert!(matches!(dao::delete_language(db.conn(), "python"), Err(dao::DaoError::InvalidInput(_))));
+
+    dao::remove_language_alias(db.conn(), "kt").unwrap();
+    assert!(!dao::language_exists(db.conn(), "kt").unwrap());
+    dao::delete_language(db.conn(), "kotlin").unwrap();
+    assert!(matches!(dao::get_language_id_from_name(db.conn(), "kotlin"), Err(dao::DaoError::NotFound(_))));
+}
+
+#[test]
+fn test_create_code_snippet_detects_language() {
+    let db = setup_test_db();
+    let snippet = |code: &str| CodeSnippet::new(String::from(code));
+
+    let id = dao::create_code_snippet(db.conn(), &snippet("let mut v: Vec<u8> = Vec::new();"), None, None).unwrap();
+    let stored = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+    assert_eq!(dao::get_language_from_id(db.conn(), stored.language_id).unwrap(), "rust");
+
+    // Shells are languages of their own, so snippets run under the shell they were written for
+    let scripts = [("#!/bin/bash\necho hi", "bash"), ("#!/bin/sh\necho hi", "sh"), ("#!/usr/bin/env zsh\nprint hi", "zsh")];
+    for (code, shell) in scripts {
+        let id = dao::create_code_snippet(db.conn(), &snippet(code), None, None).unwrap();
+        let stored = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+        assert_eq!(dao::get_language_from_id(db.conn(), stored.language_id).unwrap(), shell);
+    }
+
+    // Weak or no evidence is refused rather than guessed
+    assert!(matches!(
+        dao::create_code_snippet(db.conn(), &snippet("print(x)"), None, None),
+        Err(dao::DaoError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        dao::create_code_snippet(db.conn(), &snippet("hello"), None, None),
+        Err(dao::DaoError::InvalidInput(_))
+    ));
+
+    // Code added from a file is also detected from the file's extension
+    let id = dao::create_code_snippet(db.conn(), &snippet("x = 1"), None, Some("notes.py")).unwrap();
+    let stored = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+    assert_eq!(dao::get_language_from_id(db.conn(), stored.language_id).unwrap(), "python");
+
+    let detection = dao::detect_snippet_language(db.conn(), "x = 1", Some("notes.py")).unwrap().unwrap();
+    assert_eq!(detection.language, "python");
+    assert!(detection.is_confident());
+}
+
+#[test]
+fn test_snippet_metadata_is_stored_and_searchable() {
+    let db = setup_test_db();
+    let snippet = CodeSnippet {
+        title: Some(String::from("Email validation")),
+        description: Some(String::from("  Good enough for sign-up forms  ")),
+        source_url: Some(String::from("https://stackoverflow.com/q/201323")),
+        tags: Some(String::from("Regex, validation,regex")),
+        ..CodeSnippet::new(String::from(r"^[^@\s]+@[^@\s]+$"))
+    };
+    let id = dao::create_code_snippet(db.conn(), &snippet, Some("js"), None).unwrap();
+    let untitled = add_snippet(&db, "console.log(1)", "js");
+
+    let mut stored = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+    assert_eq!(stored.title.as_deref(), Some("Email validation"));
+    assert_eq!(stored.description.as_deref(), Some("Good enough for sign-up forms"));
+    assert_eq!(stored.tags.as_deref(), Some("regex, validation"));
+    let plain = dao::read_code_snippet(db.conn(), untitled).unwrap().unwrap();
+    assert_eq!((plain.title, plain.source_url, plain.tags), (None, None, None));
+
+    let found = |text: &str| -> Vec<i64> {
+        dao::search_code_snippets(db.conn(), text).unwrap().into_iter().map(|s| s.id).collect()
+    };
+    assert_eq!(found("EMAIL"), vec![id]);
+    assert_eq!(found("sign-up"), vec![id]);
+    assert_eq!(found("stackoverflow"), vec![id]);
+    assert_eq!(found("validation"), vec![id]);
+    assert_eq!(found(" Regex "), vec![id]);
+
+    // Tags match whole, so a tag is not found by its prefix
+    let rustacean = CodeSnippet {
+        tags: Some(String::from("rustacean")),
+        ..CodeSnippet::new(String::from("cargo new crab"))
+    };
+    let rustacean = dao::create_code_snippet(db.conn(), &rustacean, Some("bash"), None).unwrap();
+    assert_eq!(found("rustacean"), vec![rustacean]);
+    assert!(found("rust").is_empty());
+
+    stored.title = Some(String::from("  "));
+    stored.tags = Some(String::from("forms"));
+    dao::update_code_snippet(db.conn(), &stored, "js").unwrap();
+    let updated = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+    assert_eq!(updated.title, None);
+    assert_eq!(updated.tags.as_deref(), Some("forms"));
+}
+
+#[test]
+fn test_snippet_parameters_are_detected() {
+    let db = setup_test_db();
+    let id = add_snippet(&db, "docker run -p {{port:8080}}:80 {{image}}", "bash");
+
+    let mut snippet = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+    let names: Vec<(&str, Option<&str>)> =
+        snippet.parameters.iter().map(|p| (p.name.as_str(), p.default.as_deref())).collect();
+    assert_eq!(names, vec![("port", Some("8080")), ("image", None)]);
+    let stored: String = db
+        .conn()
+        .query_row("SELECT parameters FROM code_snippets WHERE id = ?1", [id], |row| row.get(0))
+        .unwrap();
+    assert_eq!(stored, r#"[{"name":"port","default":"8080"},{"name":"image","default":null}]"#);
+
+    snippet.full_code = String::from("docker ps");
+    dao::update_code_snippet(db.conn(), &snippet, "bash").unwrap();
+    assert!(dao::read_code_snippet(db.conn(), id).unwrap().unwrap().parameters.is_empty());
+
+    // Rows saved before parameters were stored get them detected on read
+    db.conn()
+        .execute("UPDATE code_snippets SET full_code = 'ssh {{host}}', parameters = NULL WHERE id = ?1", [id])
+        .unwrap();
+    let legacy = dao::read_code_snippet(db.conn(), id).unwrap().unwrap();
+    assert_eq!(legacy.parameters.len(), 1);
+    assert_eq!(legacy.parameters[0].name, "host");
+}
+
+#[test]
+fn test_snippet_runs_are_recorded() {
+    let db = setup_test_db();
+    let id = add_snippet(&db, "echo {{who:world}}", "bash");
+    dao::record_snippet_run(db.conn(), id, "echo world", Some(0), std::time::Duration::from_millis(12)).unwrap();
+    let last = dao::record_snippet_run(db.conn(), id, "echo you", Some(1), std::time::Duration::from_secs(2)).unwrap();
+
+    let runs = dao::list_snippet_runs(db.conn(), id, 10).unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].id, last);
+    assert_eq!((runs[0].command.as_str(), runs[0].exit_code, runs[0].duration_ms), ("echo you", Some(1), 2000));
+    assert_eq!(dao::count_snippet_runs(db.conn(), id).unwrap(), 2);
+
+    dao::delete_code_snippet(db.conn(), id).unwrap();
+    assert_eq!(dao::count_snippet_runs(db.conn(), id).unwrap(), 0);
+}
+
+#[test]
+fn test_link_and_unlink_snippets() {
+    let db = setup_test_db();
+    let snippet = add_snippet(&db, "git rebase -i HEAD~{{n:3}}", "bash");
+    let entry = add_entry(&db, "Cleaned up my branch history", None);
+    let dir = tempfile::tempdir().unwrap();
+    let note = add_note(&db, &dir, "git.md", "# Git\n");
+
+    dao::link_snippet(db.conn(), snippet, LinkTarget::Journal(entry), Some("squashed fixups")).unwrap();
+    dao::link_snippet(db.conn(), snippet, LinkTarget::Note(note), None).unwrap();
+
+    let links = dao::list_snippet_links(db.conn(), snippet).unwrap();
+    let targets: Vec<_> = links.iter().map(|link| link.target().unwrap()).collect();
+    assert_eq!(targets, vec![LinkTarget::Journal(entry), LinkTarget::Note(note)]);
+    assert_eq!(links[0].description.as_deref(), Some("squashed fixups"));
+    assert_eq!(links[1].description, None);
+
+    let linked = dao::list_linked_snippets(db.conn(), LinkTarget::Note(note)).unwrap();
+    assert_eq!(linked.iter().map(|link| link.snippet_id).collect::<Vec<_>>(), vec![snippet]);
+
+    // Duplicate links and missing ends are refused
+    assert!(matches!(
+        dao::link_snippet(db.conn(), snippet, LinkTarget::Note(note), None),
+        Err(dao::DaoError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        dao::link_snippet(db.conn(), snippet, LinkTarget::Journal(999), None),
+        Err(dao::DaoError::NotFound(_))
+    ));
+    assert!(matches!(
+        dao::link_snippet(db.conn(), 999, LinkTarget::Journal(entry), None),
+        Err(dao::DaoError::NotFound(_))
+    ));
+
+    dao::unlink_snippet(db.conn(), snippet, LinkTarget::Journal(entry)).unwrap();
+    assert!(matches!(
+        dao::unlink_snippet(db.conn(), snippet, LinkTarget::Journal(entry)),
+        Err(dao::DaoError::NotFound(_))
+    ));
+    assert_eq!(dao::list_snippet_links(db.conn(), snippet).unwrap().len(), 1);
+}
+
+/// Inserts rows pointing at missing parents, as databases written before foreign keys
+/// were enforced may contain.
+fn add_orphans(db: &db::Database) {
+    let conn = db.conn();
+    conn.pragma_update(None, "foreign_keys", false).unwrap();
+    conn.execute_batch(
+        "INSERT INTO code_snippets (full_code, language_id) VALUES ('fn main() { println!(\"hi\"); }', 900);
+         INSERT INTO code_snippets (full_code, language_id) VALUES ('hello world', 901);
+         INSERT INTO snippets_used (snippet_id, journal_entry_id) VALUES (902, NULL);
+         INSERT INTO snippet_runs (snippet_id, command, exit_code, duration_ms) VALUES (902, 'ls', 0, 1);",
+    )
+    .unwrap();
+    conn.pragma_update(None, "foreign_keys", true).unwrap();
+}
+
+#[test]
+fn test_find_and_repair_orphans() {
+    let db = setup_test_db();
+    assert!(dao::find_orphans(db.conn()).unwrap().is_empty());
+    let kept = add_snippet(&db, "print('kept')", "python");
+    add_orphans(&db);
+
+    let orphans = dao::find_orphans(db.conn()).unwrap();
+    let mut tables: Vec<&str> = orphans.iter().map(|orphan| orphan.table.as_str()).collect();
+    tables.sort();
+    assert_eq!(tables, vec!["code_snippets", "code_snippets", "snippet_runs", "snippets_used"]);
+
+    let repaired = dao::repair_orphans(db.conn()).unwrap();
+    assert_eq!(repaired.len(), 4);
+    assert!(dao::find_orphans(db.conn()).unwrap().is_empty());
+
+    // Orphaned snippets are kept under their detected language, or `text`
+    let languages: Vec<String> = dao::list_code_snippets(db.conn(), None, 100)
+        .unwrap()
+        .into_iter()
+        .filter(|snippet| snippet.id != kept)
+        .map(|snippet| dao::get_language_from_id(db.conn(), snippet.language_id).unwrap())
+        .collect();
+    assert_eq!(languages.len(), 2);
+    assert!(languages.contains(&"rust".to_string()));
+    assert!(languages.contains(&"text".to_string()));
+    let count: i64 = db
+        .conn()
+        .query_row("SELECT (SELECT COUNT(*) FROM snippets_used) + (SELECT COUNT(*) FROM snippet_runs)", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_learning_notes_are_indexed() {
+    let db = setup_test_db();
+    let dir = tempfile::tempdir().unwrap();
+    let content = "# Ownership\n\nEvery value has a single owner.\n\n## Borrowing\n\nReferences borrow values.\n";
+    let id = add_note(&db, &dir, "rust.md", content);
+
+    let note = dao::read_learning_note(db.conn(), id).unwrap().unwrap();
+    assert_eq!(note.title.as_deref(), Some("Ownership"));
+    assert_eq!(note.file_name, "rust.md");
+    assert_eq!(note.file_path, dir.path().canonicalize().unwrap().join("rust.md").to_str().unwrap());
+    assert_eq!(note.word_count, 1 + 6 + 1 + 3);
+    let headings: Vec<&str> = note.headings.iter().map(|heading| heading.text.as_str()).collect();
+    assert_eq!(headings, vec!["Ownership", "Borrowing"]);
+    assert_eq!(note.content_hash.as_deref(), Some(cli_notes::markdown::content_hash(content).as_str()));
+
+    // Adding the same file again, by any path, returns the existing note, re-indexed if it changed
+    std::fs::write(dir.path().join("rust.md"), "# Ownership and borrowing\n").unwrap();
+    let again = dir.path().join(".").join("rust.md");
+    assert_eq!(dao::create_learning_note(db.conn(), again.to_str().unwrap()).unwrap(), id);
+    assert_eq!(dao::list_learning_notes(db.conn()).unwrap().len(), 1);
+    let note = dao::read_learning_note(db.conn(), id).unwrap().unwrap();
+    assert_eq!(note.title.as_deref(), Some("Ownership and borrowing"));
+    assert!(dao::list_stale_learning_notes(db.conn()).unwrap().is_empty());
+
+    // Missing files are refused
+    let missing = dir.path().join("missing.md");
+    assert!(matches!(
+        dao::create_learning_note(db.conn(), missing.to_str().unwrap()),
+        Err(dao::DaoError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_search_learning_notes() {
+    let db = setup_test_db();
+    let dir = tempfile::tempdir().unwrap();
+    let rust = add_note(&db, &dir, "rust.md", "# Ownership\n\nThe borrow checker enforces borrowing rules.\n");
+    let git = add_note(&db, &dir, "git.md", "# Git\n\n## Borrowing commits\n\nCherry-pick them.\n");
+
+    let ids = |query: &str| -> Vec<i64> {
+        dao::search_learning_notes(db.conn(), query, None, ("[", "]"))
+            .unwrap()
+            .into_iter()
+            .map(|result| result.note.id)
+            .collect()
+    };
+    assert_eq!(ids("checker"), vec![rust]);
+    assert_eq!(ids("ownership"), vec![rust]);
+    let mut both = ids("borrow");
+    both.sort();
+    assert_eq!(both, vec![rust, git]);
+    assert_eq!(ids("cherry-pick?"), vec![git]);
+
+    let results = dao::search_learning_notes(db.conn(), "checker", Some(1), ("[", "]")).unwrap();
+    assert!(results[0].snippet.contains("[checker]"));
+
+    // Deleting a note removes it from the index
+    db.conn().execute("DELETE FROM learning_notes WHERE id = ?1", [rust]).unwrap();
+    assert!(ids("checker").is_empty());
+}
+
+#[test]
+fn test_import_learning_note() {
+    let db = setup_test_db();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tips.md");
+    std::fs::write(&path, "# Tips\n").unwrap();
+
+    let id = match dao::import_learning_note(db.conn(), &path).unwrap() {
+        NoteImport::Added(id) => id,
+        other => panic!("expected a new note, got {:?}", other),
+    };
+    // The same file through another path is recognized by its canonical path
+    let dotted = dir.path().join(".").join("tips.md");
+    assert_eq!(dao::import_learning_note(db.conn(), &dotted).unwrap(), NoteImport::Unchanged(id));
+
+    // A copy is recognized by its content
+    let copy = dir.path().join("copy.md");
+    std::fs::copy(&path, &copy).unwrap();
+    assert_eq!(dao::import_learning_note(db.conn(), &copy).unwrap(), NoteImport::Duplicate(id));
+
+    std::fs::write(&path, "# Better tips\n").unwrap();
+    assert_eq!(dao::import_learning_note(db.conn(), &path).unwrap(), NoteImport::Changed(id));
+    let note = dao::read_learning_note(db.conn(), id).unwrap().unwrap();
+    assert_eq!(note.title.as_deref(), Some("Better tips"));
+    let results = dao::search_learning_notes(db.conn(), "better", None, ("", "")).unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(dao::search_learning_notes(db.conn(), "tips NOT better", None, ("", "")).unwrap().is_empty());
+}
+
+#[test]
+fn test_sync_learning_notes() {
+    let db = setup_test_db();
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let edited = add_note(&db, &dir, "edited.md", "# Edited\n");
+    let moved = add_note(&db, &dir, "moved.md", "# Moved\n");
+    let deleted = add_note(&db, &dir, "deleted.md", "# Deleted\n");
+    let kept = add_note(&db, &dir, "kept.md", "# Kept\n");
+    dao::record_note_root(db.conn(), &root, "**/*.md", &[]).unwrap();
+
+    std::fs::write(root.join("edited.md"), "# Edited again\n").unwrap();
+    std::fs::create_dir(root.join("archive")).unwrap();
+    std::fs::rename(root.join("moved.md"), root.join("archive/moved.md")).unwrap();
+    std::fs::remove_file(root.join("deleted.md")).unwrap();
+
+    let stale: Vec<i64> = dao::list_stale_learning_notes(db.conn()).unwrap().iter().map(|n| n.id).collect();
+    assert_eq!(stale, vec![edited]);
+
+    let synced = dao::sync_learning_notes(db.conn()).unwrap();
+    let summary: Vec<(i64, NoteSync)> = synced.iter().map(|(note, sync)| (note.id, sync.clone())).collect();
+    let old_path = root.join("moved.md").to_string_lossy().into_owned();
+    assert_eq!(
+        summary,
+        vec![
+            (edited, NoteSync::Updated),
+            (moved, NoteSync::Moved(old_path)),
+            (deleted, NoteSync::Missing),
+            (kept, NoteSync::Unchanged),
+        ]
+    );
+    assert_eq!(synced[0].0.title.as_deref(), Some("Edited again"));
+    assert_eq!(synced[1].0.file_path, root.join("archive/moved.md").to_string_lossy());
+    assert!(synced[2].0.missing_since.is_some());
+    assert!(dao::list_stale_learning_notes(db.conn()).unwrap().is_empty());
+
+    // A missing file that comes back is restored
+    std::fs::write(root.join("deleted.md"), "# Deleted\n").unwrap();
+    let synced = dao::sync_learning_notes(db.conn()).unwrap();
+    assert_eq!(synced[2].1, NoteSync::Restored);
+    assert_eq!(synced[2].0.missing_since, None);
+}
+
+#[test]
+fn test_sync_skips_unreadable_notes() {
+    let db = setup_test_db();
+    let dir = tempfile::tempdir().unwrap();
+    let broken = add_note(&db, &dir, "a.md", "# A\n");
+    let edited = add_note(&db, &dir, "b.md", "# B\n");
+    std::fs::write(dir.path().join("a.md"), b"\xff\xfe").unwrap();
+    std::fs::write(dir.path().join("b.md"), "# B edited\n").unwrap();
+
+    let stale: Vec<i64> = dao::list_stale_learning_notes(db.conn()).unwrap().iter().map(|n| n.id).collect();
+    assert_eq!(stale, vec![broken, edited]);
+
+    let synced = dao::sync_learning_notes(db.conn()).unwrap();
+    assert!(matches!(&synced[0].1, NoteSync::Unreadable(error) if error.contains("a.md")));
+    assert_eq!(synced[0].0.title.as_deref(), Some("A"));
+    assert_eq!((synced[1].0.id, &synced[1].1), (edited, &NoteSync::Updated));
+    assert_eq!(synced[1].0.title.as_deref(), Some("B edited"));
+}
+
+#[test]
+fn test_note_links() {
+    let db = setup_test_db();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("rust")).unwrap();
+    let index = add_note(
+        &db,
+        &dir,
+        "index.md",
+        "# Index\n\n[[ownership]], [[The Borrow Checker]], [lifetimes](rust/lifetimes.md#elision) and [[Async]].\n",
+    );
+    let ownership = add_note(&db, &dir, "rust/Ownership.md", "# Ownership\n\nBack to [the index](../index.md).\n");
+    let borrowing = add_note(&db, &dir, "rust/borrowing.md", "# The borrow checker\n");
+
+    let resolved = |db: &db::Database| -> Vec<(i64, String, Option<i64>)> {
+        dao::list_note_links(db.conn())
+            .unwrap()
+            .into_iter()
+            .map(|link| (link.source_note_id, link.written(), link.target_note_id))
+            .collect()
+    };
+    assert_eq!(
+        resolved(&db),
+        vec![
+            (index, "[[ownership]]".to_string(), Some(ownership)),
+            (index, "[[The Borrow Checker]]".to_string(), Some(borrowing)),
+            (index, "rust/lifetimes.md".to_string(), None),
+            (index, "[[Async]]".to_string(), None),
+            (ownership, "../index.md".to_string(), Some(index)),
+        ]
+    );
+    let links = dao::list_note_links(db.conn()).unwrap();
+    assert_eq!(links[2].kind, NoteLinkKind::Markdown);
+
+    // Broken links resolve once the note they point at is added
+    let lifetimes = add_note(&db, &dir, "rust/lifetimes.md", "# Lifetimes\n");
+    assert_eq!(resolved(&db)[2].2, Some(lifetimes));
+
+    // Re-indexing replaces a note's links and deleting it removes them
+    std::fs::write(dir.path().join("index.md"), "# Index\n\n[[ownership]]\n").unwrap();
+    dao::import_learning_note(db.conn(), &dir.path().join("index.md")).unwrap();
+    assert_eq!(resolved(&db).len(), 2);
+    db.conn().execute("DELETE FROM learning_notes WHERE id = ?1", [ownership]).unwrap();
+    assert_eq!(resolved(&db), vec![(index, "[[ownership]]".to_string(), None)]);
+}
+
+#[test]
+fn test_links_of_notes_indexed_before_links() {
+    // A database from before migration 13, with notes that were indexed without their links
+    let db = db::Database::new(":memory:").unwrap();
+    for migration in &cli_notes::migrations::MIGRATIONS[..12] {
+        db.conn().execute_batch(migration.sql).unwrap();
+    }
+    db.conn().pragma_update(None, "user_version", 12).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let mut notes = Vec::new();
+    for (name, content) in [("a.md", "# A\n\n[[b]]\n"), ("b.md", "# B\n\n[[a]]\n")] {
+        let path = root.join(name);
+        std::fs::write(&path, content).unwrap();
+        db.conn()
+            .execute(
+                "INSERT INTO learning_notes (file_path, file_name, content_hash) VALUES (?1, ?2, ?3)",
+                rusqlite::params![path.to_str().unwrap(), name, cli_notes::markdown::content_hash(content)],
+            )
+            .unwrap();
+        notes.push(db.conn().last_insert_rowid());
+    }
+    db.initialize().unwrap();
+
+    // Their hashes are kept, so they are still known by content and not stale
+    let hash = cli_notes::markdown::content_hash("# A\n\n[[b]]\n");
+    assert_eq!(dao::find_learning_note_by_hash(db.conn(), &hash).unwrap().unwrap().id, notes[0]);
+    assert!(dao::list_stale_learning_notes(db.conn()).unwrap().is_empty());
+    assert!(dao::list_note_links(db.conn()).unwrap().is_empty());
+
+    // Finding them unchanged indexes their links
+    assert_eq!(
+        dao::import_learning_note(db.conn(), &root.join("a.md")).unwrap(),
+        NoteImport::Unchanged(notes[0])
+    );
+    assert_eq!(dao::list_note_links(db.conn()).unwrap().len(), 1);
+    let synced = dao::sync_learning_notes(db.conn()).unwrap();
+    assert!(synced.iter().all(|(_, sync)| *sync == NoteSync::Unchanged));
+    let targets: Vec<Option<i64>> = dao::list_note_links(db.conn()).unwrap().iter().map(|l| l.target_note_id).collect();
+    assert_eq!(targets, vec![Some(notes[1]), Some(notes[0])]);
+}
+
+#[test]
+fn test_notes_added_with_relative_paths() {
+    let db = setup_test_db();
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    std::fs::write(root.join("a.md"), "# A\n").unwrap();
+    std::fs::write(root.join("b.md"), "# B\n").unwrap();
+    // Before paths were canonicalized, `note add` stored the path as typed, relative to where it ran
+    let cwd = std::env::current_dir().unwrap();
+    let relative = |name: &str| {
+        let up = "../".repeat(cwd.components().count() - 1);
+        std::path::PathBuf::from(up).join(root.strip_prefix("/").unwrap()).join(name)
+    };
+    let mut old = Vec::new();
+    for path in [relative("a.md"), relative("b.md"), std::path::PathBuf::from("elsewhere/c.md")] {
+        db.conn()
+            .execute(
+                "INSERT INTO learning_notes (file_path, file_name) VALUES (?1, ?2)",
+                rusqlite::params![path.to_str().unwrap(), path.file_name().unwrap().to_str().unwrap()],
+            )
+            .unwrap();
+        old.push(db.conn().last_insert_rowid());
+    }
+
+    // Adding the file again finds the old note, stores its canonical path and indexes it
+    let a = root.join("a.md");
+    assert_eq!(dao::find_learning_note_by_path(db.conn(), a.to_str().unwrap()).unwrap().unwrap().id, old[0]);
+    assert_eq!(dao::create_learning_note(db.conn(), a.to_str().unwrap()).unwrap(), old[0]);
+    let note = dao::read_learning_note(db.conn(), old[0]).unwrap().unwrap();
+    assert_eq!((note.file_path.as_str(), note.title.as_deref()), (a.to_str().unwrap(), Some("A")));
+    assert_eq!(dao::list_learning_notes(db.conn()).unwrap().len(), 3);
+
+    // Sync does the same, and leaves notes whose relative path leads nowhere from here alone
+    let synced = dao::sync_learning_notes(db.conn()).unwrap();
+    let summary: Vec<(i64, NoteSync)> = synced.iter().map(|(note, sync)| (note.id, sync.clone())).collect();
+    assert_eq!(
+        summary,
+        vec![(old[0], NoteSync::Unchanged), (old[1], NoteSync::Updated), (old[2], NoteSync::Unresolved)]
+    );
+    assert_eq!(synced[1].0.file_path, root.join("b.md").to_str().unwrap());
+    assert!(synced[1].0.content_hash.is_some());
+    assert_eq!((synced[2].0.file_path.as_str(), synced[2].0.missing_since), ("elsewhere/c.md", None));
+}
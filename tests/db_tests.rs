@@ -0,0 +1,160 @@
+use cli_notes::db;
+use cli_notes::migrations;
+
+#[test]
+fn test_initialize_applies_all_migrations() {
+    let db = db::Database::new(":memory:").unwrap();
+    assert_eq!(migrations::current_version(db.conn()).unwrap(), 0);
+    assert_eq!(migrations::pending_migrations(db.conn()).unwrap().len(), migrations::MIGRATIONS.len());
+
+    db.initialize().unwrap();
+
+    assert_eq!(migrations::current_version(db.conn()).unwrap(), migrations::latest_version());
+    assert!(migrations::pending_migrations(db.conn()).unwrap().is_empty());
+
+    // Running the migrations again is a no-op
+    assert!(migrations::migrate(db.conn()).unwrap().is_empty());
+}
+
+#[test]
+fn test_migrate_legacy_database_keeps_data() {
+    // Databases created before migrations existed ran init.sql directly and have user_version 0
+    let db = db::Database::new(":memory:").unwrap();
+    db.conn().execute_batch(migrations::MIGRATIONS[0].sql).unwrap();
+    db.conn()
+        .execute("INSERT INTO journal_entries (entry, tags) VALUES ('kept', 'legacy')", [])
+        .unwrap();
+
+    db.initialize().unwrap();
+
+    assert_eq!(migrations::current_version(db.conn()).unwrap(), migrations::latest_version());
+    let entry: String = db
+        .conn()
+        .query_row("SELECT entry FROM journal_entries WHERE tags = 'legacy'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(entry, "kept");
+}
+
+#[test]
+fn test_migrate_rejects_newer_schema() {
+    let db = db::Database::new(":memory:").unwrap();
+    db.conn()
+        .pragma_update(None, "user_version", migrations::latest_version() + 1)
+        .unwrap();
+
+    let err = db.initialize().unwrap_err();
+    assert!(err.to_string().contains("newer than the latest known version"));
+}
+
+#[test]
+fn test_migrations_are_ordered() {
+    for pair in migrations::MIGRATIONS.windows(2) {
+        assert_eq!(pair[1].version, pair[0].version + 1);
+    }
+}
+
+#[test]
+fn test_migrate_splits_legacy_tag_strings() {
+    let db = db::Database::new(":memory:").unwrap();
+    db.conn().execute_batch(migrations::MIGRATIONS[0].sql).unwrap();
+    db.conn()
+        .execute(
+            "INSERT INTO journal_entries (entry, tags, sentiment, ai_tags) VALUES
+                ('first', 'Rust, testing', 'positive', 'rust,learning'),
+                ('second', '', 'neutral', 'general')",
+            [],
+        )
+        .unwrap();
+
+    db.initialize().unwrap();
+
+    let tags = cli_notes::dao::count_tags(db.conn(), None).unwrap();
+    assert_eq!(
+        tags,
+        vec![
+            ("learning".to_string(), 1),
+            ("rust".to_string(), 1),
+            ("testing".to_string(), 1),
+        ]
+    );
+    let links: i64 = db
+        .conn()
+        .query_row("SELECT COUNT(*) FROM entry_tags", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(links, 4);
+}
+
+#[test]
+fn test_migrate_unwraps_legacy_snippets() {
+    let db = db::Database::new(":memory:").unwrap();
+    for migration in &migrations::MIGRATIONS[..3] {
+        db.conn().execute_batch(migration.sql).unwrap();
+    }
+    db.conn().pragma_update(None, "user_version", 3).unwrap();
+    let legacy = [
+        "'''\nconsole.log(\"it''s me\");\n'''",
+        "'''\n\n'''",
+        "'''\n'''''' docstring ''''''\n'''",
+    ];
+    for code in legacy {
+        db.conn()
+            .execute("INSERT INTO code_snippets (full_code, language_id) VALUES (?1, 3)", [code])
+            .unwrap();
+    }
+
+    db.initialize().unwrap();
+
+    let mut stmt = db.conn().prepare("SELECT full_code FROM code_snippets ORDER BY id").unwrap();
+    let codes: Vec<String> = stmt
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(codes, vec!["console.log(\"it's me\");", "", "''' docstring '''"]);
+}
+
+#[test]
+fn test_foreign_keys_cascade() {
+    let db = db::Database::new(":memory:").unwrap();
+    db.initialize().unwrap();
+    let conn = db.conn();
+    conn.execute_batch(
+        "INSERT INTO journal_entries (id, entry) VALUES (1, 'entry');
+         INSERT INTO tags (id, name) VALUES (1, 'rust');
+         INSERT INTO entry_tags (journal_entry_id, tag_id, source) VALUES (1, 1, 'user');
+         INSERT INTO code_snippets (id, full_code, language_id) VALUES (1, 'ls', 1);
+         INSERT INTO snippets_used (snippet_id, journal_entry_id) VALUES (1, 1);
+         INSERT INTO snippet_runs (snippet_id, command, exit_code, duration_ms) VALUES (1, 'ls', 0, 5);",
+    )
+    .unwrap();
+    let count = |table: &str| -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    };
+
+    conn.execute("DELETE FROM journal_entries WHERE id = 1", []).unwrap();
+    assert_eq!(count("entry_tags"), 0);
+    assert_eq!(count("snippets_used"), 0);
+
+    conn.execute("DELETE FROM code_snippets WHERE id = 1", []).unwrap();
+    assert_eq!(count("snippet_runs"), 0);
+}
+
+#[test]
+fn test_foreign_keys_reject_missing_parents() {
+    let db = db::Database::new(":memory:").unwrap();
+    db.initialize().unwrap();
+    let conn = db.conn();
+
+    assert!(conn
+        .execute("INSERT INTO code_snippets (full_code, language_id) VALUES ('ls', 999)", [])
+        .is_err());
+    assert!(conn
+        .execute("INSERT INTO snippets_used (snippet_id, journal_entry_id) VALUES (999, NULL)", [])
+        .is_err());
+
+    // Languages in use cannot be deleted from under their snippets
+    conn.execute("INSERT INTO code_snippets (full_code, language_id) VALUES ('ls', 1)", [])
+        .unwrap();
+    assert!(conn.execute("DELETE FROM languages WHERE id = 1", []).is_err());
+}
@@ -0,0 +1,22 @@
+// Kept in its own test binary because it changes the process working directory.
+
+use cli_notes::db;
+use cli_notes::migrations;
+use tempfile::TempDir;
+
+#[test]
+fn test_initialize_from_unrelated_working_directory() {
+    let cwd = TempDir::new().unwrap();
+    std::env::set_current_dir(cwd.path()).unwrap();
+    assert!(!cwd.path().join("sql").exists());
+
+    let db = db::Database::new("clinotes.db").expect("Failed to create database in temp dir");
+    db.initialize().expect("Initialization should not depend on the working directory");
+
+    assert_eq!(migrations::current_version(db.conn()).unwrap(), migrations::latest_version());
+    let languages: i64 = db
+        .conn()
+        .query_row("SELECT COUNT(*) FROM languages", [], |row| row.get(0))
+        .unwrap();
+    assert!(languages > 0);
+}